@@ -24,282 +24,6419 @@
  * This also makes this file an easy introduction to Rust for the maintainers.
  ********************************************************************************************************/
 
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env;
 use std::fs::File;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::io;
 use std::io::prelude::*;
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::os::unix::process::ExitStatusExt;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
 use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
 use std::process::Command;
+use std::process::ExitStatus;
 use std::process::Stdio;
 use std::str::FromStr;
-
-const DRY_RUN: bool = false;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 /** See Options struct for ordered list of arguments to pass in
- * eg `collect-info "$server_pid" "$artifactDir"
+ * eg `collect-info "$artifactDir"`
+ *
+ * The Cassandra (or DSE) process is discovered from /proc, see discover_cassandra_processes()
+ * `--pid <pid>` (or env `collectInfoPid`) overrides the discovery, and the older `collect-info "$server_pid" "$artifactDir"` form is still accepted
+ *
+ * `--dry-run` (or env `collectInfoDryRun=true`) only writes the plan of commands to collect-info.plan.txt
+ *
+ * `collect-info archive "$artifactDir"` writes and verifies $artifactDir.tar.gz, see archive_artifact_dir()
+ * `collect-info manifest "$artifactDir"` only refreshes $artifactDir/MANIFEST, before the archive is written by the system tar
+ * `collect-info verify <bundle.tar.gz|dir>` checks a bundle against its MANIFEST and scores its completeness, see verify_bundle()
  **/
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    assert!(3 == args.len(), "Unexpected number of arguments ({}), should be 2 ($server_pid $artifactDir)", args.len());
+    if Some("archive") == env::args().nth(1).as_deref() {
+        let artifact_dir = env::args().nth(2).expect("Missing argument, should be (archive $artifactDir)");
+        match archive_artifact_dir(&artifact_dir) {
+            Ok((archive_path, entries, size)) => println!("Archived {} entries into {} ({} bytes), verified… OK", entries.len(), archive_path, size),
+            Err(error) => {
+                eprintln!("FATAL: {}", error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if Some("manifest") == env::args().nth(1).as_deref() {
+        let artifact_dir = env::args().nth(2).expect("Missing argument, should be (manifest $artifactDir)");
+        match refresh_bundle_manifest(artifact_dir.trim_end_matches('/')) {
+            Ok(listed) => println!("Listed {} files with their SHA-256 in {}… OK", listed, BUNDLE_MANIFEST),
+            Err(error) => {
+                eprintln!("FATAL: {}", error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if Some("verify") == env::args().nth(1).as_deref() {
+        let bundle = env::args().nth(2).expect("Missing argument, should be (verify $bundle.tar.gz|$artifactDir)");
+        std::process::exit(verify_bundle(&bundle));
+    }
+    let mut dry_run_arg = false;
+    let mut pid_arg: Option<String> = None;
+    let mut args: Vec<String> = Vec::new();
+    let mut all_args = env::args().skip(1);
+    while let Some(arg) = all_args.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run_arg = true,
+            "--pid" => pid_arg = all_args.next(),
+            _ => args.push(arg),
+        }
+    }
+    assert!(1 == args.len() || 2 == args.len(), "Unexpected number of arguments ({}), should be ([--dry-run] [--pid $pid] $artifactDir)", args.len());
+    let artifact_dir = args.pop().unwrap();
+    let pid_override: Option<String> = pid_arg
+        .or(args.pop())
+        .or(env::var("collectInfoPid").ok())
+        .filter(|pid| !pid.trim().is_empty());
+
+    let collection_start = SystemTime::now();
+    let processes = discover_cassandra_processes();
+    // with more than one process, and no pid override, each is collected from into instances/<name>/
+    let all_instances = pid_override.is_none() && 1 < processes.len() && "first" != env::var("collectInfoInstances").unwrap_or("all".to_string());
+    let selected = select_cassandra_process(&processes, pid_override, all_instances, &artifact_dir);
+    let cassandra_pid: String = selected.as_ref().map(|process| process.pid.clone()).unwrap_or_default();
+    let config_home: String = env::var("configHome").unwrap_or("/etc/cassandra".to_string());
+    let jmx: JmxEndpoint = discover_jmx(selected.as_ref(), &config_home);
+
+    // the manifest is validated before any credential file is written, as process::exit does not run destructors
+    let manifest: Vec<ManifestEntry> = match env::var("collectInfoManifest") {
+        Ok(ref path) if !path.is_empty() => load_manifest(path).unwrap_or_else(|error| {
+            eprintln!("FATAL: {}", error);
+            std::process::exit(1);
+        }),
+        _ => Vec::new(),
+    };
+    let commands: Vec<Cmd> = merge_manifest(COMMANDS, &manifest).unwrap_or_else(|error| {
+        eprintln!("FATAL: {}", error);
+        std::process::exit(1);
+    });
+
+    // private directory for the credential files handed to nodetool, cqlsh, dsetool and java, removed when dropped
+    let secrets: Secrets = Secrets::create().unwrap_or_else(|error| {
+        eprintln!("FATAL: {}", error);
+        std::process::exit(1);
+    });
 
     // all mandatory parameters are command line arguments
     // all parameters with defaults (or contain sensitive info) are environment variables 
-    let options: Options = Options {
+    let mut options: Options = Options {
         base_dir: &env::var("baseDir").unwrap_or("/tmp/datastax".to_string()),
-        artifact_dir: &args[2],
+        artifact_dir: &artifact_dir,
         skip_sudo: bool::from_str(&env::var("skipSudo").unwrap_or("false".to_string())).unwrap(),
         log_home: &env::var("logHome").unwrap_or("/var/log/cassandra".to_string()),
         logs_max_age_days: &env::var("logsMaxAgeDays").unwrap_or("".to_string()),
+        logs_include: &env::var("collectInfoLogsInclude").unwrap_or("*".to_string()),
+        logs_exclude: &env::var("collectInfoLogsExclude").unwrap_or("*.hprof,core,core.*".to_string()),
+        logs_max_file_mb: env_u64("collectInfoLogsMaxFileMB", 512),
+        logs_max_total_mb: env_u64("collectInfoLogsMaxTotalMB", 2048),
+        logs_truncate: bool::from_str(&env::var("collectInfoLogsTruncate").unwrap_or("true".to_string())).unwrap(),
+        logs_window_start: &env::var("collectInfoLogsWindowStart").unwrap_or("".to_string()),
+        logs_window_end: &env::var("collectInfoLogsWindowEnd").unwrap_or("".to_string()),
+        logs_window_hours: &env::var("collectInfoLogsWindowHours").unwrap_or("".to_string()),
+        logs_window_only: bool::from_str(&env::var("collectInfoLogsWindowOnly").unwrap_or("false".to_string())).unwrap(),
+        log_summary: bool::from_str(&env::var("collectInfoLogSummary").unwrap_or("true".to_string())).unwrap(),
+        gc_summary: bool::from_str(&env::var("collectInfoGcSummary").unwrap_or("true".to_string())).unwrap(),
+        nodetool_json: bool::from_str(&env::var("collectInfoNodetoolJson").unwrap_or("true".to_string())).unwrap(),
         data_dir: &env::var("data_dir").unwrap_or("".to_string()),
-        config_home: &env::var("configHome").unwrap_or("/etc/cassandra".to_string()),
-        cassandra_pid: &args[1],
+        config_home: &config_home,
+        cassandra_pid: &cassandra_pid,
         prometheus_jar: &env::var("prometheus_jar").unwrap_or("none.jar".to_string()),
-        jmx_host:  &env::var("jmxHost").unwrap_or("".to_string()),
-        jmx_port: &env::var("jmxPort").unwrap_or("7199".to_string()),
+        jmx_host: &jmx.host,
+        jmx_port: &jmx.port,
         jmx_username: &env::var("jmxUsername").unwrap_or("".to_string()),
         jmx_password: &env::var("jmxPassword").unwrap_or("".to_string()),
-        jmx_ssl: bool::from_str(&env::var("jmxSSL").unwrap_or("false".to_string())).unwrap(),
-        jmx_exporter_opts: &env::var("jmx_exporter_opts").unwrap_or("".to_string()),
+        jmx_ssl: jmx.ssl,
+        jmx_exporter_opts: &jmx.exporter_opts,
+        jmx_trail: &jmx.trail,
         nodetool_credentials: &env::var("nodetoolCredentials").unwrap_or("".to_string()),
         cqlsh_host:  &env::var("cqlsh_host").unwrap_or("localhost".to_string()),
         cqlsh_port:  &env::var("cqlsh_port").unwrap_or("9042".to_string()),
         cqlsh_opts: &env::var("cqlshOpts").unwrap_or("".to_string()),
+        cqlsh_username: &env::var("cqlshUsername").unwrap_or("".to_string()),
         cqlsh_password: &env::var("cqlshPassword").unwrap_or("".to_string()),
-        timeout_opts: &env::var("timeout_opts").unwrap_or("".to_string()),
         dse_bin_dir: &env::var("dse_bin_dir").unwrap_or("".to_string()),
         dse_conf_dir: &env::var("dse_conf_dir").unwrap_or("".to_string()),
         dt_opts: &env::var("dt_opts").unwrap_or("".to_string()),
         solr_data_dir: &env::var("solr_data_dir").unwrap_or("".to_string()),
+        default_timeout: env_u64("collectInfoTimeout", 30),
+        max_concurrency: (env_u64("collectInfoMaxConcurrency", 4) as usize).max(1),
+        dry_run: dry_run_arg || bool::from_str(&env::var("collectInfoDryRun").unwrap_or("false".to_string())).unwrap(),
+        native_facts: bool::from_str(&env::var("collectInfoNativeFacts").unwrap_or("true".to_string())).unwrap(),
+        natively_collected: HashSet::new(),
+        native_sampler: bool::from_str(&env::var("collectInfoNativeSampler").unwrap_or("true".to_string())).unwrap(),
+        sample_duration: env_u64("collectInfoSampleDuration", 60),
+        sample_interval: env_u64("collectInfoSampleInterval", 2).max(1),
+        redact: bool::from_str(&env::var("collectInfoRedact").unwrap_or("true".to_string())).unwrap(),
+        redact_rules: &env::var("collectInfoRedactRules").unwrap_or(DEFAULT_REDACT_RULES.to_string()),
+        secrets_dir: &secrets.dir,
+        java_argfiles: false,
+    };
+    if let Err(error) = write_secret_files(&options) {
+        eprintln!("FATAL: {}", error);
+        std::fs::remove_dir_all(options.secrets_dir).ok();
+        std::process::exit(1);
+    }
+    // an invalid incident window stops the collection, rather than native.logs failing into the cpReadable copy of the logs
+    if let Err(error) = log_window(&options) {
+        eprintln!("FATAL: {}", error);
+        std::fs::remove_dir_all(options.secrets_dir).ok();
+        std::process::exit(1);
+    }
+
+    check_all_commands(&commands, &options);
+
+    // host commands run once into the artifact dir, and the per instance commands once for each instance
+    let instances: Vec<Instance> = if all_instances { create_instances(&processes, &options) } else { Vec::new() };
+    if java_has_secret_args(&options) || instances.iter().any(|instance| instance.jmx.exporter_opts.contains("Password=")) {
+        options.java_argfiles = java_supports_argfiles();
+        if !options.java_argfiles {
+            println!("WARNING: java does not support @argfiles (Java 8), JmxScraper will be run by jjs to keep the JMX passwords out of the process list");
+        }
+    }
+    let (host_commands, instance_commands): (Vec<Cmd>, Vec<Cmd>) = if instances.is_empty() {
+        (commands.clone(), Vec::new())
+    } else {
+        commands.iter().cloned().partition(|cmd| !cmd.per_instance)
+    };
+    let host_facts: Vec<&NativeFact> = NATIVE_FACTS.iter().filter(|fact| instances.is_empty() || !fact.per_instance).collect();
+    let instance_facts: Vec<&NativeFact> = NATIVE_FACTS.iter().filter(|fact| fact.per_instance).collect();
+
+    let exit_code = if options.dry_run {
+        plan_all_commands(&host_commands, &host_facts, &options);
+        instances.iter().for_each(|instance| {
+            println!("Instance {} (pid {})…", instance.name, instance.pid);
+            let instance_options = instance.options(&options);
+            prepare_instance_dir(&instance_options, &options);
+            plan_all_commands(&instance_commands, &instance_facts, &instance_options);
+        });
+        EXIT_COMPLETE
+    } else {
+        // which command, or native collection, wrote each file, listed in MANIFEST
+        let mut producers: HashMap<String, String> = HashMap::new();
+        if options.native_facts {
+            options.natively_collected = collect_native_facts("OS information", host_facts.iter().filter(|fact| !fact.fallback_only), &[], &[], &options, &mut producers);
+        }
+        let sampler = if sampling_os_metrics(&options) { start_sampler(&options) } else { None };
+        if sampler.is_some() {
+            options.natively_collected.extend(SAMPLER_REPLACES.iter());
+            producers.insert(format!("{}/", bundle_path(&format!("{}/os-metrics", options.artifact_dir))), "native: /proc sampler".to_string());
+        }
+        let mut instance_options: Vec<Options> = instances.iter().map(|instance| instance.options(&options)).collect();
+        // the sampler runs alongside the commands, observing the host while they are collected
+        let (outcomes, instance_outcomes) = thread::scope(|scope| {
+            let sampling = sampler.map(|sampler| scope.spawn(|| sample_os_metrics(sampler, &options)));
+            let outcomes = execute_all_commands(&host_commands, &options);
+            let instance_outcomes: Vec<Vec<(usize, Outcome)>> = instances.iter().zip(instance_options.iter_mut()).map(|(instance, instance_options)| {
+                println!("Instance {} (pid {})…", instance.name, instance.pid);
+                prepare_instance_dir(instance_options, &options);
+                if instance_options.native_facts {
+                    let replaced = collect_native_facts("instance information", instance_facts.iter(), &[], &[], instance_options, &mut producers);
+                    instance_options.natively_collected.extend(replaced);
+                }
+                execute_all_commands(&instance_commands, instance_options)
+            }).collect();
+            if let Some(sampling) = sampling {
+                match sampling.join().unwrap() {
+                    Ok(samples) => println!("Sampled OS metrics {} times into os-metrics/… OK", samples),
+                    Err(error) => println!("WARNING: sampling OS metrics failed: {}", error),
+                }
+            }
+            (outcomes, instance_outcomes)
+        });
+        if options.native_facts {
+            collect_native_facts("missing OS information", host_facts.iter().filter(|fact| fact.fallback_only), &host_commands, &outcomes, &options, &mut producers);
+        }
+        if options.nodetool_json {
+            structure_nodetool_output(&options, &mut producers);
+            instance_options.iter().for_each(|instance_options| structure_nodetool_output(instance_options, &mut producers));
+        }
+        if options.redact {
+            redact_artifacts(&options);
+        }
+        // the worst of the host's and each instance's exit codes
+        let mut exit_code = summarise_outcomes(&host_commands, &outcomes, &options);
+        for (instance_options, outcomes) in instance_options.iter().zip(instance_outcomes.iter()) {
+            exit_code = exit_code.max(summarise_outcomes(&instance_commands, outcomes, instance_options));
+        }
+
+        let mut header = bundle_header(&options);
+        audit_completed(&options, &header[0].1);
+        record_command_producers(&host_commands, &outcomes, &options, &mut producers);
+        record_collect_info_producers(&options, &mut producers);
+        for ((instance, instance_options), outcomes) in instances.iter().zip(instance_options.iter()).zip(instance_outcomes.iter()) {
+            record_command_producers(&instance_commands, outcomes, instance_options, &mut producers);
+            record_collect_info_producers(instance_options, &mut producers);
+            header.push((format!("instance.{}.pid", instance.name), instance.pid.clone()));
+            header.push((format!("instance.{}.cassandra_version", instance.name), release_version(instance_options)));
+        }
+        header.push(("collection_start".to_string(), format_utc(collection_start)));
+        header.push(("collection_end".to_string(), format_utc(SystemTime::now())));
+        match write_bundle_manifest(options.artifact_dir, &header, &producers) {
+            Ok(files) => println!("Listed {} files with their SHA-256 in {}… OK", files, BUNDLE_MANIFEST),
+            Err(error) => println!("WARNING: {}", error),
+        }
+        exit_code
+    };
+    // process::exit does not run destructors
+    drop(secrets);
+    std::process::exit(exit_code);
+}
+
+fn check_all_commands(commands: &[Cmd], options: &Options) {
+    println!("Checking commands required to collect information…");
+
+    let mut checked_commands = HashSet::new();
+    commands.iter().for_each(|cmd| {
+
+        let cmd_str = format_command(cmd.command, options);
+        if !checked_commands.contains(&cmd_str) {
+            checked_commands.insert(cmd_str.clone());
+
+            let sudo_str = (if cmd.use_sudo && !options.skip_sudo { "sudo " } else { "" }).to_string();
+
+            print!("\tlooking for `{}{}`… ", sudo_str, cmd_str);
+            io::stdout().flush().ok().expect("Could not flush stdout");
+
+            let result = check_command(cmd, &options);
+            if result.0 {
+                println!("FOUND at {}", result.1.replace("\n", ""));
+            } else {
+                println!("missing");
+                if !cmd.optional {
+                    println!(
+                        "FATAL: {} not found and is not optional for the collector",
+                        cmd_str
+                    )
+                }
+            }
+        }
+    });
+    println!(" …OK");
+}
+
+fn execute_all_commands(commands: &[Cmd], options: &Options) -> Vec<(usize, Outcome)> {
+    println!("Collecting OS information… ");
+
+    let auditor: Mutex<File> = Mutex::new(create_auditor_file(options));
+    options.jmx_trail.iter().for_each(|line| audit(&auditor, &format!("jmx discovery: {}", line)));
+    let json_auditor: Mutex<File> = Mutex::new(create_json_auditor_file(options));
+    let outcomes: Mutex<Vec<(usize, Outcome)>> = Mutex::new(Vec::new());
+
+    let lanes = create_lanes(commands, options.max_concurrency);
+    let next_lane = AtomicUsize::new(0);
+    println!("\t{} commands in {} lanes, running up to {} at a time", commands.len(), lanes.len(), options.max_concurrency);
+
+    // each worker takes the next lane and executes its commands in order, until no lanes are left
+    thread::scope(|scope| {
+        for _ in 0..options.max_concurrency.min(lanes.len()) {
+            scope.spawn(|| loop {
+                let lane = next_lane.fetch_add(1, Ordering::SeqCst);
+                if lane >= lanes.len() {
+                    break;
+                }
+                for i in &lanes[lane] {
+                    let outcome = collect_command(*i, &commands[*i], options, &auditor, &json_auditor);
+                    outcomes.lock().unwrap().push((*i, outcome));
+                }
+            });
+        }
+    });
+
+    println!(" …OK");
+    let mut outcomes = outcomes.into_inner().unwrap();
+    outcomes.sort_by_key(|outcome| outcome.0);
+    outcomes
+}
+
+fn collect_command(i: usize, cmd: &Cmd, options: &Options, auditor: &Mutex<File>, json_auditor: &Mutex<File>) -> Outcome {
+    let cmd_str = format_command(cmd.command, options);
+    let args_str = format_args(cmd.args, options, true);
+
+    if options.natively_collected.contains(cmd.id) {
+        println!("\tskipping  `{} {}` (collected natively)", cmd_str, args_str);
+        let outcome = Outcome::Skipped("collected natively".to_string());
+        audit_json(json_auditor, i, cmd, options, &outcome, None, &[]);
+        outcome
+    } else if missing_cassandra_pid(cmd, options) {
+        println!("\tskipping  `{} {}` (no Cassandra process)", cmd_str, args_str);
+        let outcome = Outcome::Skipped("no Cassandra process".to_string());
+        audit_json(json_auditor, i, cmd, options, &outcome, None, &[]);
+        outcome
+    } else if !cmd.optional || check_command(cmd, options).0 {
+        let skipping = skipping_flags(cmd, options);
+        if skipping.is_empty() {
+            match execute_command(i, cmd, options, auditor) {
+                Ok(execution) => {
+                    let outcome = if execution.status.success() {
+                        Outcome::Collected
+                    } else {
+                        failed_outcome(cmd, describe_failure(&execution, command_timeout(cmd, options)))
+                    };
+                    audit_json(json_auditor, i, cmd, options, &outcome, Some(&execution), &skipping);
+                    println!("\texecuting `{} {} > {}`… {}", cmd_str, args_str, cmd.file, outcome.label());
+                    outcome
+                }
+                Err(error) => {
+                    let outcome = failed_outcome(cmd, error);
+                    audit_json(json_auditor, i, cmd, options, &outcome, None, &skipping);
+                    println!("\texecuting `{} {} > {}`… {}", cmd_str, args_str, cmd.file, outcome.label());
+                    outcome
+                }
+            }
+        } else {
+            println!("\texecuting `{} {} > {}`… skipped", cmd_str, args_str, cmd.file);
+            let outcome = Outcome::Skipped(format!("skip_flags: {}", skipping.join(" ")));
+            audit_json(json_auditor, i, cmd, options, &outcome, None, &skipping);
+            outcome
+        }
+    } else {
+        println!("\tskipping  `{} {}`", cmd_str, args_str);
+        let outcome = Outcome::Skipped("not found".to_string());
+        audit_json(json_auditor, i, cmd, options, &outcome, None, &[]);
+        outcome
+    }
+}
+
+// failures of required commands are fatal, failures of optional commands only degrade the collection
+fn failed_outcome(cmd: &Cmd, reason: String) -> Outcome {
+    if cmd.optional {
+        Outcome::Degraded(reason)
+    } else {
+        Outcome::Fatal(reason)
+    }
+}
+
+fn describe_failure(execution: &Execution, timeout: u64) -> String {
+    if execution.timed_out {
+        format!("timed out after {}s", timeout)
+    } else if let Some(code) = execution.status.code() {
+        format!("exit code {}", code)
+    } else if let Some(signal) = execution.status.signal() {
+        format!("killed by signal {}", signal)
+    } else {
+        "failed".to_string()
+    }
+}
+
+/** Whether any non-empty file was collected into the artifact dir, besides collect-info's own plan, audit and summary **/
+fn collected_anything(options: &Options) -> bool {
+    let mut files: Vec<PathBuf> = Vec::new();
+    list_files(Path::new(&options.artifact_dir), &mut files);
+    files.iter()
+        .filter(|path| !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("collect-info.")))
+        .any(|path| std::fs::metadata(path).is_ok_and(|metadata| 0 < metadata.len()))
+}
+
+const EXIT_COMPLETE: i32 = 0;
+const EXIT_PARTIAL: i32 = 2;
+const EXIT_NOTHING_USEFUL: i32 = 3;
+
+/** Prints the end-of-run summary, also writing it to collect-info.summary.txt, and returns the exit code
+ *  0 complete: every required command collected
+ *  2 partial: some required commands failed, but something was collected
+ *  3 nothing useful: nothing was collected, by any command or natively
+ **/
+fn summarise_outcomes(commands: &[Cmd], outcomes: &[(usize, Outcome)], options: &Options) -> i32 {
+    let count = |label: &str| outcomes.iter().filter(|outcome| outcome.1.label() == label).count();
+    let (result, exit_code) = if 0 == count("fatal") {
+        ("COMPLETE", EXIT_COMPLETE)
+    } else if collected_anything(options) {
+        ("PARTIAL", EXIT_PARTIAL)
+    } else {
+        ("NOTHING USEFUL", EXIT_NOTHING_USEFUL)
+    };
+
+    let mut summary = String::new();
+    summary.push_str("collect-info summary\n");
+    summary.push_str(&format!("\tcollected: {}\n", count("OK")));
+    summary.push_str(&format!("\tskipped:   {}\n", count("skipped")));
+    summary.push_str(&format!("\tdegraded:  {} (optional commands that failed)\n", count("degraded")));
+    summary.push_str(&format!("\tfatal:     {} (required commands that failed)\n", count("fatal")));
+    summary.push_str(&format!("result: {} (exit code {})\n\n", result, exit_code));
+    summary.push_str(&format!("{:>3}  {:<9} {:<36} {:<32} {}\n", "#", "status", "file", "reason", "command"));
+    outcomes.iter().filter(|outcome| Outcome::Collected != outcome.1).for_each(|(i, outcome)| {
+        let cmd = &commands[*i];
+        let cmd_line = format!("{} {}", format_command(cmd.command, options), format_args(cmd.args, options, true));
+        summary.push_str(&format!(
+            "{:>3}  {:<9} {:<36} {:<32} {}\n",
+            i, outcome.label(), cmd.file, outcome.reason(), cmd_line.trim_end()
+        ));
+    });
+
+    println!("\n{}", summary);
+    let summary_path = format!("{}/collect-info.summary.txt", options.artifact_dir);
+    if let Err(error) = std::fs::write(&summary_path, &summary) {
+        eprintln!("WARNING: failed to write {}: {}", summary_path, error);
+    }
+    exit_code
+}
+
+/** Groups the commands into lanes, a lane's commands are executed in order by the one worker.
+ * Commands writing to the same output file, all exclusive commands, and commands writing under a directory
+ * that etc/cpReadable.sh replaces (rm -rf and copies into, eg logs/) are put into the same lane.
+ * With a max_concurrency of one everything is one lane, in the original order.
+ **/
+fn create_lanes(commands: &[Cmd], max_concurrency: usize) -> Vec<Vec<usize>> {
+    if max_concurrency <= 1 {
+        return vec![(0..commands.len()).collect()];
+    }
+    // union-find over the command indexes
+    let mut parents: Vec<usize> = (0..commands.len()).collect();
+    let mut first_by_key: HashMap<String, usize> = HashMap::new();
+    let replaced: Vec<String> = commands.iter().filter(|cmd| cmd.command.ends_with("cpReadable.sh")).flat_map(artifact_dir_targets).collect();
+    commands.iter().enumerate().for_each(|(i, cmd)| {
+        let mut keys: Vec<String> = Vec::new();
+        if cmd.exclusive {
+            keys.push("exclusive".to_string());
+        }
+        if !cmd.file.is_empty() {
+            keys.push(format!("file:{}", cmd.file));
+        }
+        let mut paths = artifact_dir_targets(cmd);
+        if !cmd.file.is_empty() {
+            paths.push(cmd.file.to_string());
+        }
+        for directory in replaced.iter() {
+            if paths.iter().any(|path| path == directory || path.starts_with(&format!("{}/", directory))) {
+                keys.push(format!("directory:{}", directory));
+            }
+        }
+        for key in keys {
+            match first_by_key.get(&key) {
+                Some(first) => {
+                    let (a, b) = (find_lane_root(&mut parents, i), find_lane_root(&mut parents, *first));
+                    parents[a.max(b)] = a.min(b);
+                }
+                None => {
+                    first_by_key.insert(key, i);
+                }
+            }
+        }
+    });
+    let mut lanes: Vec<Vec<usize>> = Vec::new();
+    let mut lane_by_root: HashMap<usize, usize> = HashMap::new();
+    for i in 0..commands.len() {
+        let root = find_lane_root(&mut parents, i);
+        let lane = *lane_by_root.entry(root).or_insert_with(|| {
+            lanes.push(Vec::new());
+            lanes.len() - 1
+        });
+        lanes[lane].push(i);
+    }
+    lanes
+}
+
+/** The paths under the artifact dir given as arguments, eg logs for {artifact_dir}/logs **/
+fn artifact_dir_targets(cmd: &Cmd) -> Vec<String> {
+    cmd.args
+        .split_whitespace()
+        .filter_map(|arg| arg.strip_prefix("{artifact_dir}/"))
+        .map(|path| path.trim_end_matches('/').to_string())
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+fn find_lane_root(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[i] = root;
+    root
+}
+
+/** Walks the commands exactly as execute_all_commands would, but only records what would be executed.
+ * Nothing but the `command -v` checks is run.
+ **/
+fn plan_all_commands(commands: &[Cmd], facts: &[&NativeFact], options: &Options) {
+    println!("Planning commands (dry run, nothing will be executed)… ");
+
+    let mut plan: File = create_plan_file(options);
+    writeln!(plan, "# collect-info dry run, cassandra_pid={} artifact_dir={}", options.cassandra_pid, options.artifact_dir)
+        .expect("failed writing collect-info.plan.txt");
+
+    commands.iter().enumerate().for_each(|(i, cmd)| {
+        let line = format!("{:>3} {:<40} {}", i, cmd.id, plan_command(i, cmd, options));
+        println!("\t{}", line);
+        writeln!(plan, "{}", line).expect("failed writing collect-info.plan.txt");
+    });
+    if options.native_facts {
+        facts.iter().for_each(|fact| {
+            let when = if fact.fallback_only { "when it is not collected" } else { "skipping it" };
+            let replacing = if fact.replaces.is_empty() { String::new() } else { format!(", replacing {} {}", fact.replaces, when) };
+            let line = format!("{:>3} {:<40} native   read {}{}", "-", fact.id, fact.source, replacing);
+            println!("\t{}", line);
+            writeln!(plan, "{}", line).expect("failed writing collect-info.plan.txt");
+        });
+    }
+    if sampling_os_metrics(options) {
+        let line = format!(
+            "{:>3} {:<40} native   sample /proc every {}s for {}s into os-metrics/, replacing {}",
+            "-", "native.sampler", options.sample_interval, options.sample_duration, SAMPLER_REPLACES.join(" ")
+        );
+        println!("\t{}", line);
+        writeln!(plan, "{}", line).expect("failed writing collect-info.plan.txt");
+    }
+
+    println!(" …OK (plan written to {}/collect-info.plan.txt)", options.artifact_dir);
+}
+
+fn plan_command(index: usize, cmd: &Cmd, options: &Options) -> String {
+    let cmd_str = format_command(cmd.command, options);
+    let args_str = format_args(cmd.args, options, true);
+    let found = check_command(cmd, options).0;
+
+    if cmd.optional && !found {
+        return format!("skip     `{} {}` (not found)", cmd_str, args_str);
+    }
+    if missing_cassandra_pid(cmd, options) {
+        return format!("skip     `{} {}` (no Cassandra process)", cmd_str, args_str);
+    }
+    let skipping = skipping_flags(cmd, options);
+    if !skipping.is_empty() {
+        return format!("skip     `{} {}` (skip_flags: {})", cmd_str, args_str, skipping.join(" "));
+    }
+    let command = create_command(&cmd_str, cmd, options);
+    let mut line = command.get_program().to_string_lossy().to_string();
+    command.get_args().for_each(|arg| {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
+    });
+    if !args_str.is_empty() {
+        line.push(' ');
+        line.push_str(&args_str);
+    }
+    let (stdout_file, stderr_file) = output_files(index, cmd);
+    let redirect = format!(" > {} 2> {}", stdout_file, stderr_file);
+    format!(
+        "execute  `{}{}` (timeout {}s){}",
+        line, redirect, command_timeout(cmd, options), if found { "" } else { " (not found, required)" }
+    )
+}
+
+fn create_plan_file(options: &Options) -> File {
+    let plan_str = &format!("{}/collect-info.plan.txt", options.artifact_dir);
+    let plan_path = Path::new(&plan_str);
+    std::fs::create_dir_all(plan_path.parent().unwrap()).unwrap();
+    File::create(plan_path).expect("failed to create file collect-info.plan.txt")
+}
+
+fn create_auditor_file(options: &Options) -> File {
+    let auditor_str = &format!("{}/collect-info.audit.log", options.artifact_dir);
+    let auditor_path = Path::new(&auditor_str);
+    std::fs::create_dir_all(auditor_path.parent().unwrap()).unwrap();
+    File::create(&auditor_path).expect("failed to create file collect-info.audit.log")
+}
+
+fn create_json_auditor_file(options: &Options) -> File {
+    let auditor_str = &format!("{}/collect-info.audit.jsonl", options.artifact_dir);
+    let auditor_path = Path::new(&auditor_str);
+    std::fs::create_dir_all(auditor_path.parent().unwrap()).unwrap();
+    File::create(auditor_path).expect("failed to create file collect-info.audit.jsonl")
+}
+
+/** Writes one JSON line per command to collect-info.audit.jsonl
+ * The execution is only present when the command was started.
+ **/
+fn audit_json(json_auditor: &Mutex<File>, index: usize, cmd: &Cmd, options: &Options, outcome: &Outcome, execution: Option<&Execution>, skipping: &[&str]) {
+    let cmd_str = format_command(cmd.command, options);
+    let args_str = format_args(cmd.args, options, true);
+    let skip_reason = match *outcome {
+        Outcome::Skipped(ref reason) => Some(reason.as_str()),
+        _ => None,
     };
+    let error = match *outcome {
+        Outcome::Degraded(ref reason) | Outcome::Fatal(ref reason) => Some(reason.as_str()),
+        _ => None,
+    };
+
+    let mut record = JsonObject::new();
+    record
+        .number("index", index)
+        .string("command", format!("{} {}", cmd_str, args_str).trim_end())
+        .string_or_null("file", if cmd.file.is_empty() { None } else { Some(cmd.file) })
+        .string("stream", if cmd.use_stdout { "stdout" } else { "stderr" })
+        .boolean("optional", cmd.optional)
+        .boolean("sudo", cmd.use_sudo && !options.skip_sudo)
+        .number("timeout_secs", command_timeout(cmd, options))
+        .string("outcome", outcome.label())
+        .boolean("skipped", skip_reason.is_some())
+        .string_or_null("skip_reason", skip_reason)
+        .raw("skip_flags", &json_string_array(skipping))
+        .string_or_null("error", error);
+
+    if let Some(execution) = execution {
+        record
+            .string("start", &format_utc(execution.started))
+            .string("end", &format_utc(execution.ended))
+            .number("duration_ms", duration_millis(execution.started, execution.ended))
+            .boolean("success", execution.status.success())
+            .boolean("timed_out", execution.timed_out)
+            .number_or_null("exit_code", execution.status.code())
+            .number_or_null("signal", execution.status.signal())
+            .number_or_null("output_bytes", output_file_size(options.artifact_dir, cmd.file))
+            .raw("captured_files", &json_array(&execution.captured_files.iter().map(|file| json_string(file)).collect::<Vec<String>>()));
+    }
+    if let Err(error) = writeln!(json_auditor.lock().unwrap(), "{}", record.build()) {
+        eprintln!("WARNING: failed auditing to collect-info.audit.jsonl: {}", error);
+    }
+}
+
+fn output_file_size(artifact_dir: &str, cmd_file: &str) -> Option<u64> {
+    if cmd_file.is_empty() {
+        None
+    } else {
+        std::fs::metadata(format!("{}/{}", artifact_dir, cmd_file)).ok().map(|metadata| metadata.len())
+    }
+}
+
+// the skip_flags of the command that are currently set to true
+// commands for the Cassandra process can't be run when none was found
+fn missing_cassandra_pid(cmd: &Cmd, options: &Options) -> bool {
+    options.cassandra_pid.is_empty() && (cmd.command.contains("{cassandra_pid}") || cmd.args.contains("{cassandra_pid}"))
+}
+
+// jmxSSL and jmxPlain follow the discovered JMX endpoint, other flags are environment variables
+fn skipping_flags<'a>(cmd: &Cmd<'a>, options: &Options) -> Vec<&'a str> {
+    cmd.skip_flags.split_whitespace().filter(|skip_flag| match *skip_flag {
+        "jmxSSL" => options.jmx_ssl,
+        "jmxPlain" => !options.jmx_ssl,
+        _ => {
+            let val = env::var(skip_flag);
+            val.is_ok() && bool::from_str(&val.unwrap()).unwrap()
+        }
+    }).collect()
+}
+
+fn check_command(cmd: &Cmd, options: &Options) -> (bool, String) {
+    let cmd_str = format_command(cmd.command, options);
+    let mut check_cmd = create_check_command(&cmd, &options);
+
+    match check_cmd.0
+        .arg("-c")
+        .arg(format!("command -v {}", cmd_str))
+        .output()
+    {
+        Ok(result) => (result.status.success(), String::from_utf8_lossy(&result.stdout).to_string()),
+        Err(error) => {
+            eprintln!("WARNING: failed to execute `{} -c 'command -v {}'`: {}", check_cmd.1, cmd_str, error);
+            (false, "".to_string())
+        }
+    }
+}
+
+fn create_check_command(cmd: &Cmd, options: &Options) -> (Command, String) {
+  if cmd.use_sudo && !options.skip_sudo {
+    let mut command = Command::new("sudo");
+    command.arg("sh");
+    (command, "sudo sh".to_string())
+  } else {
+    (Command::new("sh"), "sh".to_string())
+  }
+}
+
+/** Executes the command, returning the error when it could not be started or waited on. **/
+fn execute_command(index: usize, cmd: &Cmd, options: &Options, auditor: &Mutex<File>) -> Result<Execution, String> {
+    let cmd_str = format_command(cmd.command, options);
+    let args_str = format_args(cmd.args, options, true);
+
+    audit(auditor, &format!("{} {} > {}", cmd_str, args_str, cmd.file));
+
+    let mut command = create_command(&cmd_str, &cmd, &options);
+    if !cmd.args.is_empty() {
+        let arg_list = format_arg_list(cmd.args, options, false);
+        match java_argfile(index, &cmd_str, &arg_list, options) {
+            Some(argfile) if options.java_argfiles => {
+                write_java_argfile(&argfile, &arg_list)?;
+                audit(auditor, &format!("arguments of `{}` passed via @{}", cmd_str, argfile));
+                command.arg(format!("@{}", argfile));
+            }
+            Some(script) => {
+                let jjs_args = write_jjs_script(&script, &arg_list)?;
+                audit(auditor, &format!("arguments of `{}` passed via jjs {}", cmd_str, script));
+                command = Command::new(Path::new(&cmd_str).with_file_name("jjs"));
+                command.args(jjs_args);
+            }
+            None => {
+                command.args(arg_list);
+            }
+        }
+    }
+    assert!(!cmd.file.is_empty() || !cmd.use_sudo, "use_sudo cannot be used when cmd.file is empty (`{} {}`)", cmd_str, args_str);
+    let (stdout_file, stderr_file) = output_files(index, cmd);
+    command.stdout(Stdio::from(create_command_output_file(options.artifact_dir, &stdout_file)?));
+    command.stderr(Stdio::from(create_command_output_file(options.artifact_dir, &stderr_file)?));
+    let started = SystemTime::now();
+    let timeout = Duration::from_secs(command_timeout(cmd, options));
+    let mut child = command
+        .process_group(0)
+        .spawn()
+        .map_err(|error| format!("failed to start: {}", error))?;
+
+    let (status, timed_out) = wait_or_kill(&mut child, timeout, cmd.use_sudo && !options.skip_sudo)
+        .map_err(|error| format!("failed waiting: {}", error))?;
+    if timed_out {
+        audit(auditor, &format!("timed out after {}s, killed `{} {}`", timeout.as_secs(), cmd_str, args_str));
+    }
+    let ended = SystemTime::now();
+
+    // keep the side output only when there was some
+    let captured_files: Vec<String> = vec![stdout_file, stderr_file]
+        .into_iter()
+        .filter(|file| *file != cmd.file)
+        .filter(|file| {
+            let path = format!("{}/{}", options.artifact_dir, file);
+            if std::fs::metadata(&path).map(|metadata| 0 == metadata.len()).unwrap_or(true) {
+                std::fs::remove_file(&path).ok();
+                false
+            } else {
+                true
+            }
+        })
+        .collect();
+
+    Ok(Execution {
+        started,
+        ended,
+        status,
+        timed_out,
+        captured_files,
+    })
+}
+
+fn audit(auditor: &Mutex<File>, line: &str) {
+    if let Err(error) = writeln!(auditor.lock().unwrap(), "{}", line) {
+        eprintln!("WARNING: failed auditing `{}`: {}", line, error);
+    }
+}
+
+/** The collector build, last in collect-info.audit.log **/
+fn audit_completed(options: &Options, collector_version: &str) {
+    let appended = std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(format!("{}/collect-info.audit.log", options.artifact_dir))
+        .and_then(|mut auditor| writeln!(auditor, "completed version {}", collector_version));
+    if let Err(error) = appended {
+        eprintln!("WARNING: failed auditing to collect-info.audit.log: {}", error);
+    }
+}
+
+/** The (stdout, stderr) files a command's output is captured to, relative to the artifact dir.
+ * The stream selected by use_stdout goes to cmd.file, the other stream to a sibling `.stderr` (or `.stdout`) file.
+ * Commands without a file have both streams captured under collect-info.output/
+ **/
+fn output_files(index: usize, cmd: &Cmd) -> (String, String) {
+    if cmd.file.is_empty() {
+        let name = Path::new(cmd.command).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let base = format!("collect-info.output/{:02}-{}", index, name);
+        (format!("{}.stdout", base), format!("{}.stderr", base))
+    } else if cmd.use_stdout {
+        (cmd.file.to_string(), format!("{}.stderr", cmd.file))
+    } else {
+        (format!("{}.stdout", cmd.file), cmd.file.to_string())
+    }
+}
+
+fn command_timeout(cmd: &Cmd, options: &Options) -> u64 {
+    if 0 < cmd.timeout {
+        cmd.timeout
+    } else {
+        options.default_timeout
+    }
+}
+
+/** Polls the child until it exits or the timeout expires.
+ * On expiry the child's whole process group is sent TERM, and KILL if still alive after a grace period.
+ * Returns the exit status and whether the timeout was hit.
+ **/
+fn wait_or_kill(child: &mut Child, timeout: Duration, use_sudo: bool) -> io::Result<(ExitStatus, bool)> {
+    let started = Instant::now();
+    let mut poll = Duration::from_millis(5);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, false));
+        }
+        if timeout <= started.elapsed() {
+            break;
+        }
+        thread::sleep(poll.min(timeout.saturating_sub(started.elapsed())));
+        poll = (poll * 2).min(Duration::from_millis(200));
+    }
+    kill_process_group(child.id(), "TERM", use_sudo);
+    let grace = Instant::now();
+    while grace.elapsed() < Duration::from_secs(5) {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, true));
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    kill_process_group(child.id(), "KILL", use_sudo);
+    child.kill().ok();
+    Ok((child.wait()?, true))
+}
+
+// the child was spawned as the leader of its own process group, so its pid is the group id
+fn kill_process_group(pgid: u32, signal: &str, use_sudo: bool) {
+    let mut kill = if use_sudo {
+        let mut command = Command::new("sudo");
+        command.arg("sh");
+        command
+    } else {
+        Command::new("sh")
+    };
+    kill.arg("-c")
+        .arg(format!("kill -s {} -- -{}", signal, pgid))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok();
+}
+
+fn format_command(cmd: &str, options: &Options) -> String {
+    cmd.replace("{base_dir}", options.base_dir)
+        .replace("{dse_bin_dir}", options.dse_bin_dir)
+}
+
+// for displaying and auditing, arguments with spaces are quoted
+fn format_args(args: &str, options: &Options, mask: bool) -> String {
+    format_arg_list(args, options, mask)
+        .iter()
+        .map(|arg| {
+            if arg.contains(char::is_whitespace) {
+                format!("'{}'", arg)
+            } else {
+                arg.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/** The args template is tokenized on whitespace first, then the {placeholders} within each token are substituted.
+ * Substituted values are never split, so they are passed verbatim even when they contain spaces.
+ * List placeholders (eg {data_dir}) expand the token once for each of their values.
+ * Tokens that end up empty (eg an unset {jmx_username}) are dropped.
+ **/
+fn format_arg_list(args: &str, options: &Options, mask: bool) -> Vec<String> {
+    let mut formatted: Vec<String> = Vec::new();
+    for token in args.split_whitespace() {
+        let mut expanded: Vec<String> = vec![String::new()];
+        let mut rest = token;
+        while !rest.is_empty() {
+            let placeholder = rest.find('{').and_then(|start| {
+                rest[start..].find('}').and_then(|length| {
+                    placeholder_values(&rest[start + 1..start + length], options, mask).map(|values| (start, length, values))
+                })
+            });
+            match placeholder {
+                Some((start, length, values)) => {
+                    let literal = &rest[..start];
+                    expanded = expanded
+                        .iter()
+                        .flat_map(|arg| values.iter().map(move |value| format!("{}{}{}", arg, literal, value)))
+                        .collect();
+                    rest = &rest[start + length + 1..];
+                }
+                None => {
+                    // no (known) placeholder left, take the literal text up to and including the next '{' if any
+                    let literal_end = rest.find('{').map(|start| start + 1).unwrap_or(rest.len());
+                    for arg in expanded.iter_mut() {
+                        arg.push_str(&rest[..literal_end]);
+                    }
+                    rest = &rest[literal_end..];
+                }
+            }
+        }
+        formatted.extend(expanded.into_iter().filter(|arg| !arg.is_empty()));
+    }
+    formatted
+}
+
+// the values of a placeholder in Cmd.args, None when it is not a known placeholder
+fn placeholder_values(name: &str, options: &Options, mask: bool) -> Option<Vec<String>> {
+    let scalar = |value: &str| Some(vec![value.to_string()]);
+    match name {
+        "artifact_dir" => scalar(options.artifact_dir),
+        "log_home" => scalar(options.log_home),
+        "logs_max_age_days" => scalar(options.logs_max_age_days),
+        "config_home" => scalar(options.config_home),
+        "cassandra_pid" => scalar(options.cassandra_pid),
+        "prometheus_jar" => scalar(options.prometheus_jar),
+        "jmx_host" => scalar(options.jmx_host),
+        "jmx_port" => scalar(options.jmx_port),
+        "jmx_username" => scalar(options.jmx_username),
+        "jmx_password" => scalar(&format_jmx_password(options.jmx_password, mask)),
+        "cqlsh_host" => scalar(options.cqlsh_host),
+        "cqlsh_port" => scalar(options.cqlsh_port),
+        "dse_conf_dir" => scalar(options.dse_conf_dir),
+        "solr_data_dir" => scalar(options.solr_data_dir),
+        // lists
+        "data_dir" => Some(options.data_dir.split(',').map(|dir| dir.trim().to_string()).filter(|dir| !dir.is_empty()).collect()),
+        "jmx_exporter_opts" => Some(split_shell_words(options.jmx_exporter_opts)),
+        "nodetool_ssl" => Some(nodetool_ssl(options.jmx_ssl)),
+        "nodetool_credentials" => Some(nodetool_credentials(options, mask)),
+        "cqlsh_opts" => Some(cqlsh_opts(options, mask)),
+        "dt_opts" => Some(dsetool_opts(options, mask)),
+        _ => None,
+    }
+}
+
+fn nodetool_ssl(jmx_ssl: bool) -> Vec<String> {
+    if jmx_ssl {
+        vec!["--ssl".to_string()]
+    } else {
+        vec![]
+    }
+}
+
+// built from jmxUsername and jmxPassword when both are set, so each is exactly one argument
+fn nodetool_credentials(options: &Options, mask: bool) -> Vec<String> {
+    if let Some(password_file) = nodetool_password_file(options) {
+        vec!["-u".to_string(), options.jmx_username.to_string(), "-pwf".to_string(), password_file]
+    } else if !options.jmx_username.is_empty() && !options.jmx_password.is_empty() {
+        vec!["-u".to_string(), options.jmx_username.to_string(), "-pw".to_string(), format_jmx_password(options.jmx_password, mask)]
+    } else {
+        split_shell_words(options.nodetool_credentials).iter().map(|opt| format_jmx_password(opt, mask)).collect()
+    }
+}
+
+// dt_opts, with its JMX credentials (-u and -pw) moved into a dserc file when possible
+fn dsetool_opts(options: &Options, mask: bool) -> Vec<String> {
+    if let Some(dserc) = dserc_file(options) {
+        let mut opts: Vec<String> = split_dsetool_credentials(options.dt_opts).0;
+        opts.push("-f".to_string());
+        opts.push(dserc);
+        return opts;
+    }
+    split_shell_words(options.dt_opts).iter().map(|opt| format_cqlsh_opts(opt, options.jmx_password, mask)).collect()
+}
+
+/** dt_opts without its -u and -pw options, and the (username, password) they gave **/
+fn split_dsetool_credentials(dt_opts: &str) -> (Vec<String>, Option<(String, String)>) {
+    let mut opts: Vec<String> = Vec::new();
+    let mut username: Option<String> = None;
+    let mut password: Option<String> = None;
+    let mut words = split_shell_words(dt_opts).into_iter();
+    while let Some(word) = words.next() {
+        match word.as_str() {
+            "-u" => username = words.next(),
+            "-pw" => password = words.next(),
+            _ => opts.push(word),
+        }
+    }
+    (opts, password.map(|password| (username.unwrap_or_default(), password)))
+}
+
+// cqlshOpts, followed by the credentials (in a cqlshrc file when possible) when cqlshUsername is set
+fn cqlsh_opts(options: &Options, mask: bool) -> Vec<String> {
+    if let Some(cqlshrc) = cqlshrc_file(options) {
+        let mut opts: Vec<String> = user_cqlsh_opts(options).0;
+        opts.push(format!("--cqlshrc={}", cqlshrc));
+        return opts;
+    }
+    let mut opts: Vec<String> = split_shell_words(options.cqlsh_opts)
+        .iter()
+        .map(|opt| format_cqlsh_opts(opt, options.cqlsh_password, mask))
+        .collect();
+    if !options.cqlsh_username.is_empty() {
+        opts.push(format!("--username={}", options.cqlsh_username));
+        opts.push(format!("--password={}", format_jmx_password(options.cqlsh_password, mask)));
+    }
+    opts
+}
+
+fn format_jmx_password(sensitive: &str, mask: bool) -> String {
+    if mask && !sensitive.is_empty() {
+        "****".to_string()
+    } else {
+        sensitive.to_string()
+    }
+}
+
+fn format_cqlsh_opts(cqlsh_opts: &str, cqlsh_password: &str, mask: bool) -> String {
+    if mask && !cqlsh_password.is_empty() {
+        cqlsh_opts.replace(cqlsh_password, "****")
+    } else {
+        cqlsh_opts.to_string()
+    }
+}
+
+/** Splits option strings (eg cqlshOpts) into words like a shell would,
+ * honouring 'single quotes', "double quotes" and backslash escapes, but without any expansion.
+ **/
+fn split_shell_words(value: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for quoted in chars.by_ref() {
+                    if '\'' == quoted {
+                        break;
+                    }
+                    word.push(quoted);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(quoted) = chars.next() {
+                    match quoted {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(escaped) if "\"\\$`".contains(escaped) => word.push(escaped),
+                            Some(escaped) => {
+                                word.push('\\');
+                                word.push(escaped);
+                            }
+                            None => word.push('\\'),
+                        },
+                        _ => word.push(quoted),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(escaped) = chars.next() {
+                    word.push(escaped);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(word.clone());
+                    word.clear();
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+fn create_command(cmd_str: &str, cmd: &Cmd, options: &Options) -> Command {
+    if cmd.use_sudo && !options.skip_sudo {
+        let mut command = Command::new("sudo");
+        command.arg(cmd_str);
+        command
+    } else {
+        Command::new(cmd_str)
+    }
+}
+
+fn create_command_output_file(artifact_dir: &str, cmd_file: &str) -> Result<File, String> {
+    let path_str: &String = &format!("{}/{}", artifact_dir, cmd_file);
+    let path = Path::new(path_str);
+    std::fs::create_dir_all(path.parent().unwrap())
+        .and_then(|_| File::create(path))
+        .map_err(|error| format!("failed to create file {}: {}", cmd_file, error))
+}
+
+#[derive(PartialEq)]
+enum Outcome {
+    Collected,
+    Skipped(String),
+    // an optional command failed
+    Degraded(String),
+    // a required command failed
+    Fatal(String),
+}
+
+impl Outcome {
+    fn label(&self) -> &'static str {
+        match *self {
+            Outcome::Collected => "OK",
+            Outcome::Skipped(_) => "skipped",
+            Outcome::Degraded(_) => "degraded",
+            Outcome::Fatal(_) => "fatal",
+        }
+    }
+
+    fn reason(&self) -> &str {
+        match *self {
+            Outcome::Collected => "",
+            Outcome::Skipped(ref reason) | Outcome::Degraded(ref reason) | Outcome::Fatal(ref reason) => reason,
+        }
+    }
+}
+
+/** A section of the manifest file, the raw `key = value` fields are applied in merge_manifest() **/
+struct ManifestEntry {
+    id: String,
+    line: usize,
+    fields: Vec<(String, String)>,
+}
+
+/** Loads the manifest, an INI-like file of sections keyed by command id, eg
+ *
+ *    # comments start with '#' or ';'
+ *    [nodetool.describering]
+ *    command = nodetool
+ *    args = -h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} describering system_auth
+ *    file = nodetool/describering.txt
+ *
+ *    [os.sar]
+ *    disabled = true
+ *
+ * Fields are those of the Cmd struct, args takes the same {placeholders} as the built-in commands.
+ **/
+fn load_manifest(path: &str) -> Result<Vec<ManifestEntry>, String> {
+    let content = std::fs::read_to_string(path).map_err(|error| format!("failed to read manifest {}: {}", path, error))?;
+    let mut entries: Vec<ManifestEntry> = Vec::new();
+    for (i, raw_line) in content.lines().enumerate() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            let id = line[1..line.len() - 1].trim().to_string();
+            if id.is_empty() || entries.iter().any(|entry| entry.id == id) {
+                return Err(format!("{}:{} empty or duplicate command id `{}`", path, i + 1, id));
+            }
+            entries.push(ManifestEntry { id, line: i + 1, fields: Vec::new() });
+        } else if let Some(equals) = line.find('=') {
+            let key = line[..equals].trim().to_string();
+            let value = line[equals + 1..].trim().to_string();
+            match entries.last_mut() {
+                Some(entry) => entry.fields.push((key, value)),
+                None => return Err(format!("{}:{} `{}` is not inside a [command-id] section", path, i + 1, key)),
+            }
+        } else {
+            return Err(format!("{}:{} expected `[command-id]` or `key = value`, found `{}`", path, i + 1, line));
+        }
+    }
+    Ok(entries)
+}
+
+/** Applies the manifest to the built-in commands.
+ * Entries with the id of a built-in command replace the fields they specify, or remove it with `disabled = true`.
+ * All other entries are appended, in manifest order.
+ **/
+fn merge_manifest<'a>(builtin: &[Cmd<'a>], manifest: &'a [ManifestEntry]) -> Result<Vec<Cmd<'a>>, String> {
+    let mut commands: Vec<Cmd<'a>> = Vec::new();
+    for cmd in builtin {
+        match manifest.iter().find(|entry| entry.id == cmd.id) {
+            Some(entry) => {
+                if !manifest_disabled(entry)? {
+                    let mut replaced = cmd.clone();
+                    apply_manifest_entry(&mut replaced, entry)?;
+                    commands.push(replaced);
+                }
+            }
+            None => commands.push(cmd.clone()),
+        }
+    }
+    for entry in manifest.iter().filter(|entry| !builtin.iter().any(|cmd| cmd.id == entry.id)) {
+        if !manifest_disabled(entry)? {
+            let mut added = Cmd {
+                id: &entry.id,
+                command: "",
+                args: "",
+                file: "",
+                optional: true,
+                skip_flags: "",
+                use_stdout: true,
+                use_sudo: false,
+                timeout: 0,
+                exclusive: false,
+                per_instance: false,
+            };
+            apply_manifest_entry(&mut added, entry)?;
+            commands.push(added);
+        }
+    }
+    if !manifest.is_empty() {
+        println!("Applied manifest, {} built-in and {} total commands", builtin.len(), commands.len());
+    }
+    Ok(commands)
+}
+
+fn manifest_disabled(entry: &ManifestEntry) -> Result<bool, String> {
+    match entry.fields.iter().find(|field| "disabled" == field.0) {
+        Some(field) => parse_manifest_bool(entry, &field.1),
+        None => Ok(false),
+    }
+}
+
+fn apply_manifest_entry<'a>(cmd: &mut Cmd<'a>, entry: &'a ManifestEntry) -> Result<(), String> {
+    for (key, value) in &entry.fields {
+        match key.as_str() {
+            "command" => cmd.command = value,
+            "args" => cmd.args = value,
+            "file" => cmd.file = value,
+            "optional" => cmd.optional = parse_manifest_bool(entry, value)?,
+            "skip_flags" => cmd.skip_flags = value,
+            "use_stdout" => cmd.use_stdout = parse_manifest_bool(entry, value)?,
+            "use_sudo" => cmd.use_sudo = parse_manifest_bool(entry, value)?,
+            "timeout" => {
+                cmd.timeout = u64::from_str(value)
+                    .map_err(|_| format!("manifest [{}] (line {}) timeout must be seconds, found `{}`", entry.id, entry.line, value))?
+            }
+            "exclusive" => cmd.exclusive = parse_manifest_bool(entry, value)?,
+            "per_instance" => cmd.per_instance = parse_manifest_bool(entry, value)?,
+            "disabled" => {}
+            _ => return Err(format!("manifest [{}] (line {}) unknown field `{}`", entry.id, entry.line, key)),
+        }
+    }
+    if cmd.command.is_empty() {
+        return Err(format!("manifest [{}] (line {}) has no command", entry.id, entry.line));
+    }
+    if cmd.use_sudo && cmd.file.is_empty() {
+        return Err(format!("manifest [{}] (line {}) use_sudo cannot be used without a file", entry.id, entry.line));
+    }
+    Ok(())
+}
+
+fn parse_manifest_bool(entry: &ManifestEntry, value: &str) -> Result<bool, String> {
+    bool::from_str(value).map_err(|_| format!("manifest [{}] (line {}) expected true or false, found `{}`", entry.id, entry.line, value))
+}
+
+/** A private (0700) temporary directory holding the credential files (0600),
+ * so that passwords are not passed as command line arguments visible to `ps`.
+ * The directory is removed when dropped.
+ **/
+struct Secrets {
+    dir: String,
+}
+
+impl Secrets {
+    fn create() -> Result<Secrets, String> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.subsec_nanos()).unwrap_or(0);
+        let dir = env::temp_dir().join(format!("collect-info-secrets-{}-{}", std::process::id(), nanos));
+        std::fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&dir)
+            .map_err(|error| format!("failed to create private directory {}: {}", dir.display(), error))?;
+        Ok(Secrets { dir: dir.to_string_lossy().to_string() })
+    }
+}
+
+impl Drop for Secrets {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+// writes the file readable only by this user
+fn write_secret_file(path: &str, content: &str) -> Result<(), String> {
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|error| format!("failed to write {}: {}", path, error))
+}
+
+/** Writes the credential files used by nodetool_credentials(), cqlsh_opts() and dsetool_opts() **/
+fn write_secret_files(options: &Options) -> Result<(), String> {
+    if let Some(password_file) = nodetool_password_file(options) {
+        write_secret_file(&password_file, &format!("{} {}\n", options.jmx_username, options.jmx_password))?;
+    }
+    if let Some(cqlshrc) = cqlshrc_file(options) {
+        // keep the operator's own cqlshrc settings (eg [ssl]), replacing only the [authentication] section
+        let base = user_cqlsh_opts(options).1;
+        let mut content = String::new();
+        let mut in_authentication = false;
+        for line in std::fs::read_to_string(&base).unwrap_or_default().lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                in_authentication = "[authentication]" == trimmed;
+            }
+            if !in_authentication {
+                content.push_str(line);
+                content.push('\n');
+            }
+        }
+        content.push_str(&format!("\n[authentication]\nusername = {}\npassword = {}\n", options.cqlsh_username, options.cqlsh_password));
+        write_secret_file(&cqlshrc, &content)?;
+    }
+    if let Some(dserc) = dserc_file(options) {
+        let (username, password) = split_dsetool_credentials(options.dt_opts).1.unwrap_or_default();
+        write_secret_file(&dserc, &format!("jmx_username={}\njmx_password={}\n", username, password))?;
+    }
+    Ok(())
+}
+
+// nodetool's -pwf file splits on whitespace, so it can't be used for passwords containing any
+fn nodetool_password_file(options: &Options) -> Option<String> {
+    if options.jmx_username.is_empty() || options.jmx_password.is_empty() {
+        None
+    } else if options.jmx_password.contains(char::is_whitespace) {
+        println!("WARNING: jmxPassword contains whitespace, it can't be put in a nodetool password file and will be visible in the process list");
+        None
+    } else {
+        Some(format!("{}/nodetool.pwf", options.secrets_dir))
+    }
+}
+
+// cqlsh's config parser interpolates '%', so passwords containing it stay on the command line
+fn cqlshrc_file(options: &Options) -> Option<String> {
+    if options.cqlsh_username.is_empty() {
+        None
+    } else if options.cqlsh_password.contains('%') || options.cqlsh_password.contains('\n') {
+        println!("WARNING: cqlshPassword can't be put in a cqlshrc file and will be visible in the process list");
+        None
+    } else {
+        Some(format!("{}/cqlshrc", options.secrets_dir))
+    }
+}
+
+// dsetool's -f file holds one `key=value` per line, so passwords containing a newline stay on the command line
+fn dserc_file(options: &Options) -> Option<String> {
+    match split_dsetool_credentials(options.dt_opts).1 {
+        None => None,
+        Some((username, password)) if username.contains('\n') || password.contains('\n') => {
+            println!("WARNING: the dt_opts credentials can't be put in a dserc file and will be visible in the process list");
+            None
+        }
+        Some(_) => Some(format!("{}/dserc", options.secrets_dir)),
+    }
+}
+
+/** cqlshOpts without any --cqlshrc option, and the cqlshrc file it named (or the default ~/.cassandra/cqlshrc) **/
+fn user_cqlsh_opts(options: &Options) -> (Vec<String>, String) {
+    let mut opts: Vec<String> = Vec::new();
+    let mut cqlshrc = format!("{}/.cassandra/cqlshrc", env::var("HOME").unwrap_or_default());
+    let mut words = split_shell_words(options.cqlsh_opts).into_iter();
+    while let Some(word) = words.next() {
+        if let Some(path) = word.strip_prefix("--cqlshrc=") {
+            cqlshrc = path.to_string();
+        } else if "--cqlshrc" == word {
+            cqlshrc = words.next().unwrap_or_default();
+        } else {
+            opts.push(word);
+        }
+    }
+    (opts, cqlshrc)
+}
+
+// JVM options like -Djavax.net.ssl.keyStorePassword=… are secrets too
+fn is_secret_arg(arg: &str, options: &Options) -> bool {
+    (!options.jmx_password.is_empty() && arg.contains(options.jmx_password)) || arg.contains("Password=")
+}
+
+fn java_has_secret_args(options: &Options) -> bool {
+    !options.jmx_password.is_empty() || options.jmx_exporter_opts.contains("Password=")
+}
+
+// @argfiles are supported from Java 9, `java -version` prints eg `version "1.8.0_292"` or `version "11.0.2"`
+fn java_supports_argfiles() -> bool {
+    match Command::new("java").arg("-version").output() {
+        Ok(output) => {
+            let version = String::from_utf8_lossy(&output.stderr).to_string();
+            let quoted = version.split('"').nth(1).unwrap_or("1");
+            let major = quoted.strip_prefix("1.").unwrap_or(quoted);
+            u32::from_str(major.split(|c: char| !c.is_ascii_digit()).next().unwrap_or("1")).map(|major| 9 <= major).unwrap_or(false)
+        }
+        Err(_) => false,
+    }
+}
+
+/** The @argfile (or jjs script without @argfile support) to pass a java command's arguments in, when any of them are secret **/
+fn java_argfile(index: usize, cmd_str: &str, arg_list: &[String], options: &Options) -> Option<String> {
+    let is_java = Path::new(cmd_str).file_name().map(|name| "java" == name).unwrap_or(false);
+    if !is_java || !arg_list.iter().any(|arg| is_secret_arg(arg, options)) {
+        None
+    } else if options.java_argfiles {
+        Some(format!("{}/java-{}.args", options.secrets_dir, index))
+    } else {
+        Some(format!("{}/java-{}.js", options.secrets_dir, index))
+    }
+}
+
+// one argument per line, quoted, see https://docs.oracle.com/en/java/javase/11/tools/java.html#GUID-4856361B-8BFD-4964-AE84-121F5F6CF111
+fn write_java_argfile(argfile: &str, arg_list: &[String]) -> Result<(), String> {
+    // from a previous instance's command of the same index
+    std::fs::remove_file(argfile).ok();
+    let content: String = arg_list
+        .iter()
+        .map(|arg| format!("\"{}\"\n", arg.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    write_secret_file(argfile, &content)
+}
+
+/** Java 8 has no @argfiles, so a java command with secret arguments is run by jjs (Nashorn) from a script holding them.
+ * The script sets the -D properties with System.setProperty() then calls the main class with the program arguments,
+ * returning jjs's own arguments (the classpath, other JVM options as -J, and the script).
+ **/
+fn write_jjs_script(script: &str, arg_list: &[String]) -> Result<Vec<String>, String> {
+    // from a previous instance's command of the same index
+    std::fs::remove_file(script).ok();
+    let mut jjs_args: Vec<String> = Vec::new();
+    let mut content = String::new();
+    let mut main_class: Option<&String> = None;
+    let mut args = arg_list.iter();
+    while let Some(arg) = args.next() {
+        if "-cp" == arg || "-classpath" == arg {
+            jjs_args.push("-cp".to_string());
+            jjs_args.push(args.next().cloned().unwrap_or_default());
+        } else if let Some(property) = arg.strip_prefix("-D") {
+            let (name, value) = property.split_once('=').unwrap_or((property, ""));
+            content.push_str(&format!("java.lang.System.setProperty({}, {});\n", json_string(name), json_string(value)));
+        } else if arg.starts_with('-') {
+            jjs_args.push(format!("-J{}", arg));
+        } else {
+            main_class = Some(arg);
+            break;
+        }
+    }
+    let main_class = main_class.ok_or("no main class to run with jjs")?;
+    let program_args: Vec<String> = args.map(|arg| json_string(arg)).collect();
+    content.push_str(&format!("Java.type({}).main(Java.to([{}], \"java.lang.String[]\"));\n", json_string(main_class), program_args.join(", ")));
+    write_secret_file(script, &content)?;
+    jjs_args.push(script.to_string());
+    Ok(jjs_args)
+}
+
+/** A Cassandra or DSE JVM found in /proc **/
+#[derive(Clone)]
+struct CassandraProcess {
+    pid: String,
+    // cassandra or dse
+    kind: &'static str,
+    user: String,
+    started: Option<SystemTime>,
+    // the arguments, from /proc/<pid>/cmdline
+    args: Vec<String>,
+    config_dir: String,
+    log_dir: String,
+    jmx_port: String,
+}
+
+impl CassandraProcess {
+    // the value of a -D system property
+    fn property(&self, name: &str) -> Option<&str> {
+        let prefix = format!("-D{}=", name);
+        self.args.iter().rev().find(|arg| arg.starts_with(&prefix)).map(|arg| &arg[prefix.len()..])
+    }
+}
+
+const CASSANDRA_MAIN_CLASSES: &[(&str, &str)] = &[("org.apache.cassandra.service.CassandraDaemon", "cassandra"), ("com.datastax.bdp.DseModule", "dse")];
+
+/** Finds the Cassandra and DSE JVMs by their main class in /proc/<pid>/cmdline, sorted by pid **/
+fn discover_cassandra_processes() -> Vec<CassandraProcess> {
+    let mut pids: Vec<u32> = std::fs::read_dir("/proc")
+        .map(|entries| entries.filter_map(|entry| entry.ok()).filter_map(|entry| u32::from_str(&entry.file_name().to_string_lossy()).ok()).collect())
+        .unwrap_or_default();
+    pids.sort();
+    pids.iter().filter(|pid| std::process::id() != **pid).filter_map(|pid| describe_cassandra_process(&pid.to_string(), false)).collect()
+}
+
+/** The process, None when it isn't a Cassandra or DSE JVM (unless `any` process is wanted) **/
+fn describe_cassandra_process(pid: &str, any: bool) -> Option<CassandraProcess> {
+    let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    let args: Vec<String> = String::from_utf8_lossy(&cmdline).split('\0').filter(|arg| !arg.is_empty()).map(|arg| arg.to_string()).collect();
+    let is_java = args.first().map(|program| program.ends_with("java")).unwrap_or(false);
+    let kind = CASSANDRA_MAIN_CLASSES.iter().find(|main_class| args.iter().any(|arg| arg == main_class.0)).map(|main_class| main_class.1);
+    let kind = match kind {
+        Some(kind) if is_java => kind,
+        _ if any => "unknown",
+        _ => return None,
+    };
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).unwrap_or_default();
+    let uid = status.lines().find(|line| line.starts_with("Uid:")).and_then(|line| line.split_whitespace().nth(1)).unwrap_or("").to_string();
+    let environ = String::from_utf8_lossy(&std::fs::read(format!("/proc/{}/environ", pid)).unwrap_or_default()).to_string();
+    let variable = |name: &str| environ.split('\0').find(|variable| variable.starts_with(&format!("{}=", name))).map(|variable| variable[name.len() + 1..].to_string());
+
+    let mut process = CassandraProcess {
+        pid: pid.to_string(),
+        kind,
+        user: user_name(&uid),
+        started: process_start_time(pid),
+        args,
+        config_dir: String::new(),
+        log_dir: String::new(),
+        jmx_port: String::new(),
+    };
+    // -Dcassandra.config=file:///etc/cassandra/cassandra.yaml, else CASSANDRA_CONF, else the classpath directory with a cassandra.yaml
+    process.config_dir = process
+        .property("cassandra.config")
+        .map(|config| config.trim_start_matches("file://"))
+        .and_then(|config| Path::new(config).parent())
+        .map(|dir| dir.to_string_lossy().to_string())
+        .or(variable("CASSANDRA_CONF"))
+        .or_else(|| {
+            let classpath = process.args.iter().position(|arg| "-cp" == arg || "-classpath" == arg).and_then(|i| process.args.get(i + 1))?;
+            classpath.split(':').find(|dir| Path::new(dir).join("cassandra.yaml").exists()).map(|dir| dir.to_string())
+        })
+        .unwrap_or_default();
+    process.log_dir = process.property("cassandra.logdir").map(|dir| dir.to_string()).unwrap_or_default();
+    process.jmx_port = process.property("cassandra.jmx.local.port").or(process.property("cassandra.jmx.remote.port")).unwrap_or("").to_string();
+    Some(process)
+}
+
+// the name of the uid, from /etc/passwd
+fn user_name(uid: &str) -> String {
+    std::fs::read_to_string("/etc/passwd")
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<&str>>())
+        .find(|fields| 2 < fields.len() && fields[2] == uid)
+        .map(|fields| fields[0].to_string())
+        .unwrap_or(uid.to_string())
+}
+
+// the boot time (btime of /proc/stat) plus the start time in ticks (field 22 of /proc/<pid>/stat)
+fn process_start_time(pid: &str) -> Option<SystemTime> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let ticks = u64::from_str(stat[stat.rfind(')')? + 1..].split_whitespace().nth(19)?).ok()?;
+    let boot = std::fs::read_to_string("/proc/stat").ok()?;
+    let btime = u64::from_str(boot.lines().find(|line| line.starts_with("btime "))?[6..].trim()).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(btime) + Duration::from_millis((ticks as f64 / USER_HZ * 1000.0) as u64))
+}
+
+/** Picks the process to collect from, the pid override else the only (or first) discovered,
+ * printing and writing all candidates to collect-info.processes.txt
+ **/
+fn select_cassandra_process(processes: &[CassandraProcess], pid_override: Option<String>, all_instances: bool, artifact_dir: &str) -> Option<CassandraProcess> {
+    let selected: Option<CassandraProcess> = match pid_override {
+        Some(pid) => {
+            let process = processes.iter().find(|process| process.pid == pid).cloned().or(describe_cassandra_process(&pid, true));
+            if process.as_ref().map(|process| "unknown" == process.kind).unwrap_or(true) {
+                println!("WARNING: the given pid {} is not a running Cassandra or DSE process", pid);
+            }
+            process.or(Some(CassandraProcess {
+                pid,
+                kind: "unknown",
+                user: String::new(),
+                started: None,
+                args: Vec::new(),
+                config_dir: String::new(),
+                log_dir: String::new(),
+                jmx_port: String::new(),
+            }))
+        }
+        None => {
+            if processes.is_empty() {
+                println!("WARNING: no Cassandra or DSE process found, commands for the process will be skipped");
+            } else if all_instances {
+                println!("{} Cassandra or DSE processes found, collecting from each into instances/", processes.len());
+            } else if 1 < processes.len() {
+                println!("WARNING: {} Cassandra or DSE processes found, collecting from the first (set collectInfoPid to pick another)", processes.len());
+            }
+            processes.first().cloned()
+        }
+    };
+
+    let mut report = format!("{:<3} {:<8} {:<10} {:<12} {:<25} {:<9} {:<36} {}\n", "", "pid", "kind", "user", "started", "jmx port", "config dir", "log dir");
+    let mut candidates: Vec<&CassandraProcess> = processes.iter().collect();
+    if let Some(ref selected) = selected {
+        if !processes.iter().any(|process| process.pid == selected.pid) {
+            candidates.push(selected);
+        }
+    }
+    for process in candidates {
+        let is_selected = all_instances || selected.as_ref().map(|selected| selected.pid == process.pid).unwrap_or(false);
+        let row = format!(
+            "{:<3} {:<8} {:<10} {:<12} {:<25} {:<9} {:<36} {}",
+            if is_selected { "*" } else { "" }, process.pid, process.kind, process.user,
+            process.started.map(format_utc).unwrap_or_default(), process.jmx_port, process.config_dir, process.log_dir
+        );
+        report.push_str(&format!("{}\n", row.trim_end()));
+    }
+    println!("Cassandra processes (* collected from)…\n{}", report);
+    if let Err(error) = create_command_output_file(artifact_dir, "collect-info.processes.txt").and_then(|mut file| {
+        file.write_all(report.as_bytes()).map_err(|error| format!("failed to write collect-info.processes.txt: {}", error))
+    }) {
+        eprintln!("WARNING: {}", error);
+    }
+    selected
+}
+
+/** The JMX endpoint of a Cassandra process, and the trail of how it was decided (written to the audit log) **/
+struct JmxEndpoint {
+    host: String,
+    port: String,
+    ssl: bool,
+    exporter_opts: String,
+    trail: Vec<String>,
+}
+
+const JMX_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+// for probing all the candidates, after which the first candidate is used
+const JMX_PROBE_BUDGET: Duration = Duration::from_secs(10);
+
+// the last uncommented assignment of the variable in a shell script, eg JMX_PORT="7199" in cassandra-env.sh
+fn shell_variable(script: &str, name: &str) -> Option<String> {
+    script
+        .lines()
+        .map(|line| line.trim())
+        .rfind(|line| line.starts_with(&format!("{}=", name)))
+        .map(|line| line[name.len() + 1..].split(" #").next().unwrap_or("").trim().trim_matches(|c| '"' == c || '\'' == c).to_string())
+}
+
+fn probe_tcp(host: &str, port: &str) -> bool {
+    let port = match u16::from_str(port) {
+        Ok(port) => port,
+        Err(_) => return false,
+    };
+    (host, port)
+        .to_socket_addrs()
+        .map(|addresses| addresses.into_iter().any(|address| TcpStream::connect_timeout(&address, JMX_PROBE_TIMEOUT).is_ok()))
+        .unwrap_or(false)
+}
+
+/** Decides the JMX host, port, ssl and JmxScraper options for the process.
+ * Configured values (collectInfoJmxHost, collectInfoJmxPort, collectInfoJmxSSL) are used as they are,
+ * otherwise the candidates from the process's command line, its cassandra-env.sh, and the defaults,
+ * are probed in that order (within JMX_PROBE_BUDGET), and the first reachable used.
+ **/
+fn discover_jmx(process: Option<&CassandraProcess>, config_home: &str) -> JmxEndpoint {
+    let mut trail: Vec<String> = Vec::new();
+    let configured = |name: &str| env::var(name).ok().filter(|value| !value.trim().is_empty());
+    let property = |name: &str| process.and_then(|process| process.property(name)).map(|value| value.to_string());
+    let pid = process.map(|process| process.pid.as_str()).unwrap_or("none");
+
+    let config_dir = process.map(|process| process.config_dir.as_str()).filter(|dir| !dir.is_empty()).unwrap_or(config_home);
+    let cassandra_env = std::fs::read_to_string(format!("{}/cassandra-env.sh", config_dir)).unwrap_or_default();
+    let local_jmx = shell_variable(&cassandra_env, "LOCAL_JMX").map(|local| "no" != local).unwrap_or(true);
+    trail.push(format!("pid {}, config dir {}, LOCAL_JMX={}", pid, config_dir, if local_jmx { "yes" } else { "no" }));
+
+    let mut ports: Vec<(String, &str)> = Vec::new();
+    let mut hosts: Vec<(String, &str)> = Vec::new();
+    let candidate = |candidates: &mut Vec<(String, &'static str)>, value: Option<String>, source: &'static str| {
+        if let Some(value) = value.filter(|value| !value.is_empty()) {
+            if !candidates.iter().any(|candidate| candidate.0 == value) {
+                candidates.push((value, source));
+            }
+        }
+    };
+    candidate(&mut ports, configured("collectInfoJmxPort"), "configured");
+    candidate(&mut ports, property("cassandra.jmx.local.port"), "-Dcassandra.jmx.local.port");
+    candidate(&mut ports, property("cassandra.jmx.remote.port"), "-Dcassandra.jmx.remote.port");
+    candidate(&mut ports, property("com.sun.management.jmxremote.port"), "-Dcom.sun.management.jmxremote.port");
+    candidate(&mut ports, shell_variable(&cassandra_env, "JMX_PORT"), "cassandra-env.sh JMX_PORT");
+    candidate(&mut ports, configured("jmxPort"), "ds-collector");
+    candidate(&mut ports, Some("7199".to_string()), "default");
+
+    let remote = !local_jmx || property("cassandra.jmx.remote.port").is_some();
+    candidate(&mut hosts, configured("collectInfoJmxHost"), "configured");
+    if remote {
+        candidate(&mut hosts, property("java.rmi.server.hostname"), "-Djava.rmi.server.hostname");
+    }
+    candidate(&mut hosts, Some("127.0.0.1".to_string()), "default");
+    candidate(&mut hosts, Some("localhost".to_string()), "default");
+    candidate(&mut hosts, Some(read_trimmed("/proc/sys/kernel/hostname")), "hostname");
+    if !remote {
+        candidate(&mut hosts, property("java.rmi.server.hostname"), "-Djava.rmi.server.hostname");
+    }
+    candidate(&mut hosts, configured("jmxHost"), "ds-collector");
+
+    let describe = |candidates: &[(String, &str)]| candidates.iter().map(|candidate| format!("{} ({})", candidate.0, candidate.1)).collect::<Vec<String>>().join(", ");
+    trail.push(format!("candidate ports: {}", describe(&ports)));
+    trail.push(format!("candidate hosts: {}", describe(&hosts)));
+
+    let (host, port) = if configured("collectInfoJmxHost").is_some() && configured("collectInfoJmxPort").is_some() {
+        let reachable = probe_tcp(&hosts[0].0, &ports[0].0);
+        trail.push(format!("using configured {}:{} ({})", hosts[0].0, ports[0].0, if reachable { "reachable" } else { "NOT reachable" }));
+        (hosts[0].0.clone(), ports[0].0.clone())
+    } else {
+        let mut found: Option<(String, String)> = None;
+        let probing = Instant::now();
+        'probing: for port in ports.iter() {
+            for host in hosts.iter() {
+                if JMX_PROBE_BUDGET <= probing.elapsed() {
+                    trail.push(format!("stopped probing after {}s", JMX_PROBE_BUDGET.as_secs()));
+                    break 'probing;
+                }
+                let reachable = probe_tcp(&host.0, &port.0);
+                trail.push(format!("probed {}:{}… {}", host.0, port.0, if reachable { "reachable" } else { "unreachable" }));
+                if reachable {
+                    found = Some((host.0.clone(), port.0.clone()));
+                    break 'probing;
+                }
+            }
+        }
+        match found {
+            Some((host, port)) => {
+                trail.push(format!("using {}:{}", host, port));
+                (host, port)
+            }
+            None => {
+                trail.push(format!("nothing reachable, using {}:{}", hosts[0].0, ports[0].0));
+                (hosts[0].0.clone(), ports[0].0.clone())
+            }
+        }
+    };
+
+    // collectInfoJmxSSL, when set, overrides the process's property
+    let ssl_configured = configured("collectInfoJmxSSL").map(|ssl| "true" == ssl.trim());
+    let ssl_property = property("com.sun.management.jmxremote.ssl").map(|ssl| "true" == ssl);
+    let ssl = ssl_configured.or(ssl_property).unwrap_or(false);
+    let describe_ssl = |ssl: Option<bool>| ssl.map(|ssl| ssl.to_string()).unwrap_or("unset".to_string());
+    trail.push(format!("ssl {} (configured {}, -Dcom.sun.management.jmxremote.ssl {})", ssl, describe_ssl(ssl_configured), describe_ssl(ssl_property)));
+
+    // the process's own key and trust stores, for JmxScraper's ssl connection
+    let mut exporter_opts: Vec<String> = Vec::new();
+    if ssl {
+        for name in ["javax.net.ssl.keyStore", "javax.net.ssl.keyStorePassword", "javax.net.ssl.trustStore", "javax.net.ssl.trustStorePassword"].iter() {
+            if let Some(value) = property(name) {
+                exporter_opts.push(format!("'-D{}={}'", name, value.replace('\'', "'\\''")));
+                trail.push(format!("JmxScraper option -D{} from the command line", name));
+            }
+        }
+    }
+    let exporter_opts = if exporter_opts.is_empty() { configured("jmx_exporter_opts").unwrap_or_default() } else { exporter_opts.join(" ") };
+
+    if property("com.sun.management.jmxremote.authenticate").map(|authenticate| "true" == authenticate).unwrap_or(false) && configured("jmxUsername").is_none() {
+        trail.push("WARNING JMX authentication is enabled but jmxUsername and jmxPassword are not configured".to_string());
+    }
+    println!("JMX endpoint for pid {}…\n\t{}", pid, trail.join("\n\t"));
+    JmxEndpoint { host, port, ssl, exporter_opts, trail }
+}
+
+/** A Cassandra process collected from into its own instances/<name>/ directory,
+ * with the settings that differ between instances taken from its command line and cassandra.yaml
+ **/
+struct Instance {
+    name: String,
+    pid: String,
+    artifact_dir: String,
+    jmx: JmxEndpoint,
+    config_home: String,
+    log_home: String,
+    data_dir: String,
+    cqlsh_port: String,
+}
+
+impl Instance {
+    // the host's options, with this instance's settings
+    fn options<'a>(&'a self, host: &Options<'a>) -> Options<'a> {
+        let mut options = host.clone();
+        options.artifact_dir = &self.artifact_dir;
+        options.cassandra_pid = &self.pid;
+        options.jmx_host = &self.jmx.host;
+        options.jmx_port = &self.jmx.port;
+        options.jmx_ssl = self.jmx.ssl;
+        options.jmx_exporter_opts = &self.jmx.exporter_opts;
+        options.jmx_trail = &self.jmx.trail;
+        options.config_home = &self.config_home;
+        options.log_home = &self.log_home;
+        options.data_dir = &self.data_dir;
+        options.cqlsh_port = &self.cqlsh_port;
+        options
+    }
+}
+
+/** One Instance for each process, named after its config dir (eg /etc/cassandra-a → cassandra-a),
+ * or its JMX port (then pid) when the config dirs' names are not unique.
+ **/
+fn create_instances(processes: &[CassandraProcess], options: &Options) -> Vec<Instance> {
+    let config_name = |process: &CassandraProcess| Path::new(&process.config_dir).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let names: Vec<String> = processes.iter().map(config_name).collect();
+    let ports: Vec<&str> = processes.iter().map(|process| process.jmx_port.as_str()).collect();
+    let unique = |values: &[&str]| values.iter().all(|value| !value.is_empty() && 1 == values.iter().filter(|other| *other == value).count());
+
+    processes
+        .iter()
+        .enumerate()
+        .map(|(i, process)| {
+            let name = if unique(&names.iter().map(|name| name.as_str()).collect::<Vec<&str>>()) {
+                names[i].clone()
+            } else if unique(&ports) {
+                format!("jmx-{}", ports[i])
+            } else {
+                format!("pid-{}", process.pid)
+            };
+            let config_home = if process.config_dir.is_empty() { options.config_home.to_string() } else { process.config_dir.clone() };
+            let (configured, data_dirs) = read_cassandra_yaml(&config_home);
+            Instance {
+                artifact_dir: format!("{}/instances/{}", options.artifact_dir, name),
+                name,
+                pid: process.pid.clone(),
+                jmx: discover_jmx(Some(process), &config_home),
+                log_home: if process.log_dir.is_empty() { options.log_home.to_string() } else { process.log_dir.clone() },
+                data_dir: data_dirs.join(","),
+                cqlsh_port: configured.get("native_transport_port").cloned().unwrap_or(options.cqlsh_port.to_string()),
+                config_home,
+            }
+        })
+        .collect()
+}
+
+// the instance's directory, with the cql scripts (written by ds-collector into the artifact dir) the commands execute
+fn prepare_instance_dir(instance_options: &Options, host: &Options) {
+    let result = std::fs::create_dir_all(format!("{}/sstable-statistics", instance_options.artifact_dir)).and_then(|_| {
+        for script in ["execute_schema.cql", "execute_metadata.cql"].iter() {
+            let source = format!("{}/{}", host.artifact_dir, script);
+            if Path::new(&source).exists() {
+                std::fs::copy(&source, format!("{}/{}", instance_options.artifact_dir, script))?;
+            }
+        }
+        Ok(())
+    });
+    if let Err(error) = result {
+        eprintln!("WARNING: failed to prepare {}: {}", instance_options.artifact_dir, error);
+    }
+}
+
+/** Facts collected by reading /proc and /sys directly, for hosts (eg container images) without the tools.
+ * Facts replacing a command are collected before the commands are executed, and the command is skipped.
+ * Facts that are `fallback_only` are collected afterwards, only when the command (giving more information) was not collected.
+ **/
+struct NativeFact {
+    id: &'static str,
+    source: &'static str,
+    // id of the command collecting the equivalent file(s), empty when there is none
+    replaces: &'static str,
+    fallback_only: bool,
+    // collected for each instance, into its directory
+    per_instance: bool,
+    // directory the fact copies files into, besides the files it returns, empty when none
+    copies_into: &'static str,
+    // returns the (file, content) pairs to write into the artifact dir
+    collect: fn(&Options) -> io::Result<FactFiles>,
+}
+
+type FactFiles = Vec<(String, String)>;
+
+const NATIVE_FACTS: &[NativeFact] = &[
+    NativeFact { id: "native.proc-files", source: "/proc/{cpuinfo,meminfo,interrupts,version}", replaces: "os.proc-files", fallback_only: false, per_instance: false, copies_into: "", collect: native_proc_files },
+    NativeFact { id: "native.free", source: "/proc/meminfo", replaces: "os.free", fallback_only: false, per_instance: false, copies_into: "", collect: native_free },
+    NativeFact { id: "native.uptime", source: "/proc/uptime /proc/loadavg", replaces: "os.uptime", fallback_only: false, per_instance: false, copies_into: "", collect: native_uptime },
+    NativeFact { id: "native.transparent_hugepage-enabled", source: "/sys/kernel/mm", replaces: "os.transparent_hugepage-enabled", fallback_only: false, per_instance: false, copies_into: "", collect: native_transparent_hugepage_enabled },
+    NativeFact { id: "native.transparent_hugepage-defrag", source: "/sys/kernel/mm", replaces: "os.transparent_hugepage-defrag", fallback_only: false, per_instance: false, copies_into: "", collect: native_transparent_hugepage_defrag },
+    NativeFact { id: "native.numactl-hardware", source: "/sys/devices/system/node", replaces: "os.numactl-hardware", fallback_only: false, per_instance: false, copies_into: "", collect: native_numa },
+    NativeFact { id: "native.logs", source: "logHome", replaces: "logs", fallback_only: false, per_instance: true, copies_into: "logs/", collect: native_copy_logs },
+    NativeFact { id: "native.log-summary", source: "logHome system.log* debug.log*", replaces: "", fallback_only: false, per_instance: true, copies_into: "", collect: native_log_summary },
+    NativeFact { id: "native.gc-summary", source: "logHome gc*.log*", replaces: "", fallback_only: false, per_instance: true, copies_into: "", collect: native_gc_summary },
+    NativeFact { id: "native.logs-window", source: "logHome system.log* debug.log*", replaces: "", fallback_only: false, per_instance: true, copies_into: "logs-window/", collect: native_logs_window },
+    NativeFact { id: "native.conf", source: "configHome", replaces: "conf", fallback_only: false, per_instance: true, copies_into: "conf/", collect: native_copy_conf },
+    NativeFact { id: "native.storage-topology", source: "/proc/<pid>/mountinfo /sys/block", replaces: "", fallback_only: false, per_instance: true, copies_into: "", collect: native_storage_topology },
+    // lscpu adds caches and vulnerabilities, and sudo sysctl reads the entries only root can
+    NativeFact { id: "native.lscpu", source: "/proc/cpuinfo /sys/devices/system", replaces: "os.lscpu", fallback_only: true, per_instance: false, copies_into: "", collect: native_lscpu },
+    NativeFact { id: "native.sysctl", source: "/proc/sys", replaces: "os.sysctl", fallback_only: true, per_instance: false, copies_into: "", collect: native_sysctl },
+];
+
+/** Collects the native facts, unless the command they replace was collected, returning the ids of the commands they replaced **/
+fn collect_native_facts<'f, I: Iterator<Item = &'f &'f NativeFact>>(what: &str, facts: I, commands: &[Cmd], outcomes: &[(usize, Outcome)], options: &Options, producers: &mut HashMap<String, String>) -> HashSet<&'static str> {
+    println!("Collecting {} natively from /proc and /sys… ", what);
+    let mut replaced: HashSet<&'static str> = HashSet::new();
+    for fact in facts {
+        let collected = outcomes.iter().any(|(i, outcome)| commands[*i].id == fact.replaces && Outcome::Collected == *outcome);
+        if collected {
+            continue;
+        }
+        let result = (fact.collect)(options).map_err(|error| error.to_string()).and_then(|files| {
+            for (file, content) in files.iter() {
+                create_command_output_file(options.artifact_dir, file)?
+                    .write_all(content.as_bytes())
+                    .map_err(|error| format!("failed to write {}: {}", file, error))?;
+                producers.insert(bundle_path(&format!("{}/{}", options.artifact_dir, file)), format!("native: {}", fact.source));
+            }
+            if !fact.copies_into.is_empty() {
+                producers.insert(format!("{}/", bundle_path(&format!("{}/{}", options.artifact_dir, fact.copies_into))), format!("native: {}", fact.source));
+            }
+            Ok(files.iter().map(|file| file.0.as_str()).collect::<Vec<&str>>().join(" "))
+        });
+        match result {
+            Ok(files) => {
+                if files.is_empty() {
+                    // nothing to write, eg when disabled
+                    println!("\treading {}… skipped", fact.source);
+                } else {
+                    println!("\treading {} > {}… OK", fact.source, files);
+                }
+                if !fact.replaces.is_empty() {
+                    replaced.insert(fact.replaces);
+                }
+            }
+            Err(error) => println!("\treading {}… failed: {}", fact.source, error),
+        }
+    }
+    println!(" …OK");
+    replaced
+}
+
+fn native_proc_files(_options: &Options) -> io::Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+    for path in ["/proc/cpuinfo", "/proc/meminfo", "/proc/interrupts", "/proc/version", "/etc/fstab", "/etc/security/limits.conf"].iter() {
+        // /proc files report a size of zero, so are read rather than copied
+        match std::fs::read(path) {
+            Ok(content) => files.push((format!("os/{}", path.rsplit('/').next().unwrap()), String::from_utf8_lossy(&content).to_string())),
+            // the /etc files are often missing from container images
+            Err(_) if path.starts_with("/etc/") => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(files)
+}
+
+// the kB values of /proc/meminfo (or a node's meminfo, whose lines are prefixed `Node N`)
+fn read_meminfo(path: &str) -> io::Result<HashMap<String, u64>> {
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.rsplitn(2, ':');
+            let value = parts.next()?.trim().trim_end_matches("kB").trim();
+            let key = parts.next()?.split_whitespace().last()?;
+            u64::from_str(value).ok().map(|value| (key.to_string(), value))
+        })
+        .collect())
+}
+
+// as `free -m`
+fn native_free(_options: &Options) -> io::Result<Vec<(String, String)>> {
+    let meminfo = read_meminfo("/proc/meminfo")?;
+    let kb = |key: &str| *meminfo.get(key).unwrap_or(&0);
+    let buff_cache = kb("Buffers") + kb("Cached") + kb("SReclaimable");
+    let used = kb("MemTotal").saturating_sub(kb("MemFree") + buff_cache);
+    let swap_used = kb("SwapTotal").saturating_sub(kb("SwapFree"));
+    let mut free = format!("{:<7}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n", "", "total", "used", "free", "shared", "buff/cache", "available");
+    free.push_str(&format!(
+        "{:<7}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
+        "Mem:", kb("MemTotal") / 1024, used / 1024, kb("MemFree") / 1024, kb("Shmem") / 1024, buff_cache / 1024, kb("MemAvailable") / 1024
+    ));
+    free.push_str(&format!("{:<7}{:>12}{:>12}{:>12}\n", "Swap:", kb("SwapTotal") / 1024, swap_used / 1024, kb("SwapFree") / 1024));
+    Ok(vec![("os/free.txt".to_string(), free)])
+}
+
+// as `uptime`, the time of day is in UTC and the number of users is not known
+fn native_uptime(_options: &Options) -> io::Result<Vec<(String, String)>> {
+    let uptime = std::fs::read_to_string("/proc/uptime")?;
+    let loadavg = std::fs::read_to_string("/proc/loadavg")?;
+    let up_secs = f64::from_str(uptime.split_whitespace().next().unwrap_or("0")).unwrap_or(0.0) as u64;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0)).as_secs() % 86400;
+    let days = match up_secs / 86400 {
+        0 => String::new(),
+        1 => "1 day, ".to_string(),
+        days => format!("{} days, ", days),
+    };
+    let load: Vec<&str> = loadavg.split_whitespace().take(3).collect();
+    Ok(vec![(
+        "os/uptime.txt".to_string(),
+        format!(
+            " {:02}:{:02}:{:02} up {}{:2}:{:02},  load average: {}\n",
+            now / 3600, now % 3600 / 60, now % 60, days, up_secs % 86400 / 3600, up_secs % 3600 / 60, load.join(", ")
+        ),
+    )])
+}
+
+fn native_transparent_hugepage_enabled(_options: &Options) -> io::Result<Vec<(String, String)>> {
+    Ok(vec![("os/transparent_hugepage-enabled.txt".to_string(), std::fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled")?)])
+}
+
+fn native_transparent_hugepage_defrag(_options: &Options) -> io::Result<Vec<(String, String)>> {
+    Ok(vec![("os/transparent_hugepage-defrag.txt".to_string(), std::fs::read_to_string("/sys/kernel/mm/transparent_hugepage/defrag")?)])
+}
+
+// the numbered entries, eg node0 node1 of /sys/devices/system/node, sorted by number
+fn numbered_entries(dir: &str, prefix: &str) -> io::Result<Vec<u32>> {
+    let mut numbers: Vec<u32> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix(prefix).and_then(|number| u32::from_str(number).ok())
+        })
+        .collect();
+    numbers.sort();
+    Ok(numbers)
+}
+
+fn read_trimmed(path: &str) -> String {
+    std::fs::read_to_string(path).map(|value| value.trim().to_string()).unwrap_or_default()
+}
+
+// expands a cpulist, eg `0-3,8` into 0 1 2 3 8
+fn expand_cpulist(cpulist: &str) -> Vec<u32> {
+    cpulist
+        .split(',')
+        .filter_map(|range| {
+            let mut bounds = range.trim().splitn(2, '-');
+            let first = u32::from_str(bounds.next()?).ok()?;
+            let last = bounds.next().and_then(|last| u32::from_str(last).ok()).unwrap_or(first);
+            Some(first..=last)
+        })
+        .flatten()
+        .collect()
+}
+
+// as `numactl --hardware`
+fn native_numa(_options: &Options) -> io::Result<Vec<(String, String)>> {
+    let nodes = numbered_entries("/sys/devices/system/node", "node")?;
+    let node_list: Vec<String> = nodes.iter().map(|node| node.to_string()).collect();
+    let mut numa = format!("available: {} nodes ({})\n", nodes.len(), node_list.join(" "));
+    for node in nodes.iter() {
+        let dir = format!("/sys/devices/system/node/node{}", node);
+        let cpus: Vec<String> = expand_cpulist(&read_trimmed(&format!("{}/cpulist", dir))).iter().map(|cpu| cpu.to_string()).collect();
+        let meminfo = read_meminfo(&format!("{}/meminfo", dir)).unwrap_or_default();
+        numa.push_str(&format!("node {} cpus: {}\n", node, cpus.join(" ")));
+        numa.push_str(&format!("node {} size: {} MB\n", node, meminfo.get("MemTotal").unwrap_or(&0) / 1024));
+        numa.push_str(&format!("node {} free: {} MB\n", node, meminfo.get("MemFree").unwrap_or(&0) / 1024));
+    }
+    numa.push_str("node distances:\nnode ");
+    nodes.iter().for_each(|node| numa.push_str(&format!("{:>4}", node)));
+    numa.push('\n');
+    for node in nodes.iter() {
+        numa.push_str(&format!("{:>3}: ", node));
+        read_trimmed(&format!("/sys/devices/system/node/node{}/distance", node))
+            .split_whitespace()
+            .for_each(|distance| numa.push_str(&format!("{:>4}", distance)));
+        numa.push('\n');
+    }
+    Ok(vec![("os/numactl-hardware.txt".to_string(), numa)])
+}
+
+// as `lscpu`, without caches and vulnerabilities
+fn native_lscpu(_options: &Options) -> io::Result<Vec<(String, String)>> {
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo")?;
+    let first: HashMap<&str, &str> = cpuinfo
+        .split("\n\n")
+        .next()
+        .unwrap_or("")
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ':');
+            Some((parts.next()?.trim(), parts.next()?.trim()))
+        })
+        .collect();
+    let online = read_trimmed("/sys/devices/system/cpu/online");
+    let cpus = expand_cpulist(&online);
+    // (package, core) of each online cpu
+    let mut sockets: HashSet<String> = HashSet::new();
+    let mut cores: HashSet<(String, String)> = HashSet::new();
+    for cpu in cpus.iter() {
+        let topology = format!("/sys/devices/system/cpu/cpu{}/topology", cpu);
+        let package = read_trimmed(&format!("{}/physical_package_id", topology));
+        cores.insert((package.clone(), read_trimmed(&format!("{}/core_id", topology))));
+        sockets.insert(package);
+    }
+    let numa_nodes = numbered_entries("/sys/devices/system/node", "node").unwrap_or_default();
+
+    let mut lscpu = String::new();
+    let mut field = |name: &str, value: &str| {
+        if !value.is_empty() {
+            lscpu.push_str(&format!("{:<33}{}\n", format!("{}:", name), value));
+        }
+    };
+    field("Architecture", env::consts::ARCH);
+    field("Byte Order", if cfg!(target_endian = "little") { "Little Endian" } else { "Big Endian" });
+    field("CPU(s)", &cpus.len().to_string());
+    field("On-line CPU(s) list", &online);
+    field("Thread(s) per core", &(cpus.len() / cores.len().max(1)).to_string());
+    field("Core(s) per socket", &(cores.len() / sockets.len().max(1)).to_string());
+    field("Socket(s)", &sockets.len().to_string());
+    field("NUMA node(s)", &numa_nodes.len().to_string());
+    field("Vendor ID", first.get("vendor_id").unwrap_or(&""));
+    field("CPU family", first.get("cpu family").unwrap_or(&""));
+    field("Model", first.get("model").unwrap_or(&""));
+    field("Model name", first.get("model name").or(first.get("Processor")).unwrap_or(&""));
+    field("Stepping", first.get("stepping").unwrap_or(&""));
+    field("CPU MHz", first.get("cpu MHz").unwrap_or(&""));
+    field("CPU max MHz", &read_trimmed("/sys/devices/system/cpu/cpu0/cpufreq/cpuinfo_max_freq").parse::<u64>().map(|khz| format!("{:.4}", khz as f64 / 1000.0)).unwrap_or_default());
+    field("BogoMIPS", first.get("bogomips").or(first.get("BogoMIPS")).unwrap_or(&""));
+    field("Hypervisor vendor", if first.get("flags").map(|flags| flags.contains("hypervisor")).unwrap_or(false) { "(detected)" } else { "" });
+    for node in numa_nodes.iter() {
+        field(&format!("NUMA node{} CPU(s)", node), &read_trimmed(&format!("/sys/devices/system/node/node{}/cpulist", node)));
+    }
+    field("Flags", first.get("flags").or(first.get("Features")).unwrap_or(&""));
+    Ok(vec![("os/lscpu.txt".to_string(), lscpu)])
+}
+
+// as `sysctl -a`, only the entries readable by this user
+fn native_sysctl(_options: &Options) -> io::Result<Vec<(String, String)>> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    list_files(Path::new("/proc/sys"), &mut files);
+    files.sort();
+    let mut sysctl = String::new();
+    for path in files {
+        let key = path.strip_prefix("/proc/sys").unwrap_or(&path).to_string_lossy().replace('/', ".");
+        if let Ok(value) = std::fs::read_to_string(&path) {
+            value.lines().for_each(|line| sysctl.push_str(&format!("{} = {}\n", key, line)));
+        }
+    }
+    if sysctl.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "nothing readable in /proc/sys"));
+    }
+    Ok(vec![("os/sysctl.txt".to_string(), sysctl)])
+}
+
+/** The directories Cassandra writes to, as (role, path), from cassandra.yaml
+ * defaulting like Cassandra to the cassandra.storagedir system property, then the process's $CASSANDRA_HOME/data (or /var/lib/cassandra as packaged).
+ * The data directories given to collect-info (data_dir) take precedence.
+ **/
+fn cassandra_directories(options: &Options) -> Vec<(String, String)> {
+    let (configured, mut data_dirs) = read_cassandra_yaml(options.config_home);
+    let cmdline = std::fs::read(format!("/proc/{}/cmdline", options.cassandra_pid)).unwrap_or_default();
+    let storage_dir = String::from_utf8_lossy(&cmdline)
+        .split('\0')
+        .find_map(|arg| arg.strip_prefix("-Dcassandra.storagedir="))
+        .map(|dir| dir.to_string())
+        .or_else(|| process_variable(options.cassandra_pid, "CASSANDRA_HOME").map(|home| format!("{}/data", home)))
+        .unwrap_or("/var/lib/cassandra".to_string());
+    if !options.data_dir.is_empty() {
+        data_dirs = options.data_dir.split(',').map(|dir| dir.trim().to_string()).filter(|dir| !dir.is_empty()).collect();
+    }
+    if data_dirs.is_empty() {
+        data_dirs.push(format!("{}/data", storage_dir));
+    }
+    let mut directories: Vec<(String, String)> = data_dirs.into_iter().map(|dir| ("data".to_string(), dir)).collect();
+    for (role, key, default) in [
+        ("commitlog", "commitlog_directory", "commitlog"),
+        ("hints", "hints_directory", "hints"),
+        ("saved_caches", "saved_caches_directory", "saved_caches"),
+        ("cdc", "cdc_raw_directory", "cdc_raw"),
+    ].iter() {
+        let dir = configured.get(*key).cloned().unwrap_or(format!("{}/{}", storage_dir, default));
+        directories.push((role.to_string(), dir));
+    }
+    directories
+}
+
+// an environment variable of the process, from /proc/<pid>/environ
+fn process_variable(pid: &str, name: &str) -> Option<String> {
+    let environ = std::fs::read(format!("/proc/{}/environ", pid)).ok()?;
+    let prefix = format!("{}=", name);
+    String::from_utf8_lossy(&environ).split('\0').find_map(|variable| variable.strip_prefix(&prefix).map(|value| value.to_string()))
+}
+
+/** The top level scalar settings of the config dir's cassandra.yaml, and its data_file_directories
+ * as a block sequence (`- /a` lines) or a flow sequence (`[/a, /b]`)
+ **/
+fn read_cassandra_yaml(config_home: &str) -> (HashMap<String, String>, Vec<String>) {
+    parse_cassandra_yaml(&std::fs::read_to_string(format!("{}/cassandra.yaml", config_home)).unwrap_or_default())
+}
+
+fn parse_cassandra_yaml(yaml: &str) -> (HashMap<String, String>, Vec<String>) {
+    let unquote = |value: &str| value.split(" #").next().unwrap_or("").trim().trim_matches(|c| '"' == c || '\'' == c).to_string();
+    let mut configured: HashMap<String, String> = HashMap::new();
+    let mut data_dirs: Vec<String> = Vec::new();
+    let mut in_data_dirs = false;
+    for line in yaml.lines() {
+        if in_data_dirs && line.trim_start().starts_with('-') {
+            data_dirs.push(unquote(&line.trim_start()[1..]));
+            continue;
+        }
+        in_data_dirs = false;
+        if let Some(value) = line.strip_prefix("data_file_directories:") {
+            let value = value.split(" #").next().unwrap_or("").trim();
+            match value.strip_prefix('[').and_then(|value| value.strip_suffix(']')) {
+                Some(flow) => data_dirs.extend(flow.split(',').map(unquote).filter(|dir| !dir.is_empty())),
+                None => in_data_dirs = true,
+            }
+        } else if !line.starts_with(' ') && !line.starts_with('#') {
+            let mut parts = line.splitn(2, ':');
+            if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+                if !unquote(value).is_empty() {
+                    configured.insert(key.trim().to_string(), unquote(value));
+                }
+            }
+        }
+    }
+    (configured, data_dirs)
+}
+
+/** A mount of /proc/<pid>/mountinfo **/
+struct Mount {
+    // major:minor
+    device_number: String,
+    mount_point: String,
+    options: String,
+    fs_type: String,
+    source: String,
+    super_options: String,
+}
+
+// mountinfo escapes spaces, tabs, newlines and backslashes as octal, eg \040
+fn unescape_mountinfo(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut unescaped: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if b'\\' == bytes[i] && i + 3 < bytes.len() && bytes[i + 1..i + 4].iter().all(|byte| b'0' <= *byte && *byte <= b'7') {
+            unescaped.push(u8::from_str_radix(&field[i + 1..i + 4], 8).unwrap_or(b'?'));
+            i += 4;
+        } else {
+            unescaped.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&unescaped).to_string()
+}
+
+/** The mounts as the process sees them (it may be in another mount namespace, eg a container), or as collect-info does without a process
+ * see proc(5): id parent major:minor root mount_point options [optional…] - fs_type source super_options
+ **/
+fn read_mountinfo(pid: &str) -> io::Result<Vec<Mount>> {
+    let path = if pid.is_empty() { "/proc/self/mountinfo".to_string() } else { format!("/proc/{}/mountinfo", pid) };
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| {
+            let mut halves = line.splitn(2, " - ");
+            let fields: Vec<&str> = halves.next()?.split(' ').collect();
+            let fs: Vec<&str> = halves.next()?.split(' ').collect();
+            Some(Mount {
+                device_number: fields.get(2)?.to_string(),
+                mount_point: unescape_mountinfo(fields.get(4)?),
+                options: fields.get(5)?.to_string(),
+                fs_type: fs.first()?.to_string(),
+                source: unescape_mountinfo(fs.get(1).unwrap_or(&"")),
+                super_options: fs.get(2).unwrap_or(&"").to_string(),
+            })
+        })
+        .collect())
+}
+
+// the last mounted (so visible) of the mounts with the longest mount point containing the path
+fn find_mount<'a>(mounts: &'a [Mount], path: &Path) -> Option<&'a Mount> {
+    mounts
+        .iter()
+        .enumerate()
+        .filter(|(_, mount)| path.starts_with(&mount.mount_point))
+        .max_by_key(|(i, mount)| (Path::new(&mount.mount_point).components().count(), *i))
+        .map(|(_, mount)| mount)
+}
+
+const QUEUE_PARAMETERS: &[&str] = &["rotational", "read_ahead_kb", "scheduler", "nr_requests", "max_sectors_kb", "logical_block_size", "physical_block_size"];
+
+/** A block device in the chain from a filesystem to the disks, eg dm-0 (LVM) → md0 (raid) → sda1 (partition) → sda **/
+struct BlockDevice {
+    name: String,
+    kind: &'static str,
+    // the LVM or dm name, eg vg0-data
+    dm_name: String,
+    size_bytes: u64,
+    queue: Vec<(&'static str, String)>,
+    // the devices this one is built on, eg the members of an md array, or the disk of a partition
+    slaves: Vec<String>,
+}
+
+// the block device of major:minor, through /sys/dev/block
+fn block_device_name(device_number: &str) -> Option<String> {
+    let sys_path = std::fs::canonicalize(format!("/sys/dev/block/{}", device_number)).ok()?;
+    sys_path.file_name().map(|name| name.to_string_lossy().to_string())
+}
+
+fn describe_block_device(name: &str) -> BlockDevice {
+    let sys_path = std::fs::canonicalize(format!("/sys/class/block/{}", name)).unwrap_or(PathBuf::from(format!("/sys/class/block/{}", name)));
+    let is_partition = sys_path.join("partition").exists();
+    // partitions use the queue of their disk, which is the parent directory
+    let parent = sys_path.parent().and_then(|parent| parent.file_name()).map(|parent| parent.to_string_lossy().to_string()).unwrap_or_default();
+    let queue_dir = if is_partition { sys_path.parent().map(|parent| parent.join("queue")) } else { Some(sys_path.join("queue")) };
+    let slaves: Vec<String> = if is_partition {
+        vec![parent]
+    } else {
+        let mut slaves: Vec<String> = std::fs::read_dir(sys_path.join("slaves"))
+            .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.file_name().to_string_lossy().to_string()).collect())
+            .unwrap_or_default();
+        slaves.sort();
+        slaves
+    };
+    let kind = if is_partition {
+        "partition"
+    } else if name.starts_with("dm-") {
+        "dm"
+    } else if name.starts_with("md") {
+        "md"
+    } else if name.starts_with("nvme") {
+        "nvme"
+    } else if name.starts_with("loop") {
+        "loop"
+    } else {
+        "disk"
+    };
+    BlockDevice {
+        name: name.to_string(),
+        kind,
+        dm_name: read_trimmed(&sys_path.join("dm/name").to_string_lossy()),
+        // the size is always in 512 byte sectors
+        size_bytes: u64::from_str(&read_trimmed(&sys_path.join("size").to_string_lossy())).unwrap_or(0) * 512,
+        queue: QUEUE_PARAMETERS
+            .iter()
+            .map(|parameter| (*parameter, queue_dir.as_ref().map(|dir| read_trimmed(&dir.join(parameter).to_string_lossy())).unwrap_or_default()))
+            .collect(),
+        slaves,
+    }
+}
+
+// the device and everything it is built on, depth first
+fn block_device_chain(name: &str, chain: &mut Vec<BlockDevice>) {
+    if chain.iter().any(|device| device.name == name) {
+        return;
+    }
+    let device = describe_block_device(name);
+    let slaves = device.slaves.clone();
+    chain.push(device);
+    slaves.iter().for_each(|slave| block_device_chain(slave, chain));
+}
+
+/** Native replacement of etc/cpReadable.sh for logs/ and conf/
+ *
+ * Files are copied newest first, within a per-file and a total byte budget (logs only, collectInfoLogsMaxFileMB and collectInfoLogsMaxTotalMB),
+ * oversized logs keeping their last (newest) lines when collectInfoLogsTruncate is true.
+ * Symlinks are followed only when they resolve inside the source tree, and nothing is deleted from the target.
+ * What was copied, truncated and skipped (and why) is listed in COPY-REPORT.txt in the target directory.
+ **/
+struct CopyRules {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    max_age_days: Option<u64>,
+    // 0 is unlimited
+    max_file_bytes: u64,
+    max_total_bytes: u64,
+    truncate: bool,
+}
+
+const MIB: u64 = 1024 * 1024;
+
+// a whole number (eg of MiB, or seconds) from the environment, unset or empty is the default, as is anything else (eg "1G" or "30s") with a warning
+fn env_u64(name: &str, default: u64) -> u64 {
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => u64::from_str(value.trim()).unwrap_or_else(|_| {
+            println!("WARNING: {}=\"{}\" is not a whole number, using {}", name, value, default);
+            default
+        }),
+        _ => default,
+    }
+}
+
+fn native_copy_logs(options: &Options) -> io::Result<Vec<(String, String)>> {
+    let patterns = |patterns: &str| patterns.split(',').map(|pattern| pattern.trim().to_string()).filter(|pattern| !pattern.is_empty()).collect::<Vec<String>>();
+    let mut exclude = patterns(options.logs_exclude);
+    if options.logs_window_only && log_window(options)?.is_some() {
+        // logs-window/ holds the window of system.log* and debug.log*, the other logs (eg gc.log*, audit/) are still copied
+        exclude.extend(["system.log*".to_string(), "debug.log*".to_string()]);
+    }
+    let rules = CopyRules {
+        include: patterns(options.logs_include),
+        exclude,
+        max_age_days: u64::from_str(options.logs_max_age_days.trim()).ok(),
+        max_file_bytes: options.logs_max_file_mb * MIB,
+        max_total_bytes: options.logs_max_total_mb * MIB,
+        truncate: options.logs_truncate,
+    };
+    let report = copy_tree(options.log_home, &format!("{}/logs", options.artifact_dir), &rules)?;
+    Ok(vec![("logs/COPY-REPORT.txt".to_string(), report)])
+}
+
+fn native_copy_conf(options: &Options) -> io::Result<Vec<(String, String)>> {
+    let rules = CopyRules { include: Vec::new(), exclude: Vec::new(), max_age_days: None, max_file_bytes: 0, max_total_bytes: 0, truncate: false };
+    let report = copy_tree(options.config_home, &format!("{}/conf", options.artifact_dir), &rules)?;
+    Ok(vec![("conf/COPY-REPORT.txt".to_string(), report)])
+}
+
+/** A file found in the source tree that passed the filters, still subject to the budgets **/
+struct CopyCandidate {
+    source: PathBuf,
+    relative: String,
+    size: u64,
+    modified: SystemTime,
+}
+
+/** Copies the source tree into target, returning the COPY-REPORT.txt content **/
+fn copy_tree(source: &str, target: &str, rules: &CopyRules) -> io::Result<String> {
+    let root = std::fs::canonicalize(source)?;
+    if !root.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not a directory", source)));
+    }
+    let mut candidates: Vec<CopyCandidate> = Vec::new();
+    let mut skipped: Vec<(String, String)> = Vec::new();
+    let mut visited: HashSet<PathBuf> = HashSet::new();
+    visited.insert(root.clone());
+    find_copy_candidates(&root, &root, "", rules, &mut visited, &mut candidates, &mut skipped);
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.modified));
+
+    let budget = |bytes: u64| if 0 == bytes { "unlimited".to_string() } else { format!("{} MiB", bytes / MIB) };
+    let mut report = format!("# copied from {} to {}\n", root.display(), target);
+    report += &format!(
+        "# include: {}, exclude: {}, max age: {}, per-file budget: {}{}, total budget: {}\n",
+        if rules.include.is_empty() { "*".to_string() } else { rules.include.join(",") },
+        if rules.exclude.is_empty() { "none".to_string() } else { rules.exclude.join(",") },
+        rules.max_age_days.map(|days| format!("{} days", days)).unwrap_or("none".to_string()),
+        budget(rules.max_file_bytes),
+        if rules.truncate && 0 < rules.max_file_bytes { " (larger logs keep their last lines)" } else { "" },
+        budget(rules.max_total_bytes),
+    );
+    report += "\n# newest first: copied bytes, of size, modified, path\n";
+    let (mut copied, mut truncated, mut total) = (0, 0, 0);
+    for candidate in candidates.iter() {
+        let mut keep = candidate.size;
+        if 0 < rules.max_file_bytes && rules.max_file_bytes < candidate.size {
+            // compressed (rotated) logs can't be cut
+            let compressed = [".gz", ".zip", ".bz2", ".xz", ".zst"].iter().any(|extension| candidate.relative.ends_with(extension));
+            if !rules.truncate || compressed {
+                skipped.push((candidate.relative.clone(), format!("{} bytes, larger than the per-file budget", candidate.size)));
+                continue;
+            }
+            keep = rules.max_file_bytes;
+        }
+        if 0 < rules.max_total_bytes && rules.max_total_bytes < total + keep {
+            skipped.push((candidate.relative.clone(), format!("{} bytes, over the total budget", keep)));
+            continue;
+        }
+        match copy_file_tail(&candidate.source, &Path::new(target).join(&candidate.relative), candidate.size, keep) {
+            Ok(bytes) => {
+                total += bytes;
+                copied += 1;
+                if keep < candidate.size {
+                    truncated += 1;
+                }
+                report += &format!(
+                    "{:>12}  {:>12}  {}  {}{}\n",
+                    bytes,
+                    candidate.size,
+                    format_utc(candidate.modified),
+                    candidate.relative,
+                    if keep < candidate.size { "  (truncated, kept the last lines)" } else { "" }
+                );
+            }
+            Err(error) => skipped.push((candidate.relative.clone(), format!("failed to copy: {}", error))),
+        }
+    }
+    report += "\n# skipped: path, reason\n";
+    skipped.sort();
+    skipped.iter().for_each(|(path, reason)| report += &format!("{}  {}\n", path, reason));
+    report += &format!("\n{} files copied ({} bytes, {} truncated), {} skipped\n", copied, total, truncated, skipped.len());
+    Ok(report)
+}
+
+fn find_copy_candidates(dir: &Path, root: &Path, prefix: &str, rules: &CopyRules, visited: &mut HashSet<PathBuf>, candidates: &mut Vec<CopyCandidate>, skipped: &mut Vec<(String, String)>) {
+    let mut entries: Vec<(PathBuf, String)> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| (entry.path(), entry.file_name().to_string_lossy().to_string())).collect(),
+        Err(error) => {
+            skipped.push((format!("{}/", prefix.trim_end_matches('/')), format!("not readable: {}", error)));
+            return;
+        }
+    };
+    entries.sort_by(|a, b| a.1.cmp(&b.1));
+    let now = SystemTime::now();
+    for (path, name) in entries {
+        let relative = format!("{}{}", prefix, name);
+        let metadata = match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => match std::fs::canonicalize(&path) {
+                Ok(resolved) if resolved.starts_with(root) => match std::fs::metadata(&resolved) {
+                    Ok(metadata) => metadata,
+                    Err(error) => {
+                        skipped.push((relative, format!("not readable: {}", error)));
+                        continue;
+                    }
+                },
+                Ok(resolved) => {
+                    skipped.push((relative, format!("symlink outside the source tree (-> {})", resolved.display())));
+                    continue;
+                }
+                Err(_) => {
+                    skipped.push((relative, "broken symlink".to_string()));
+                    continue;
+                }
+            },
+            Ok(metadata) => metadata,
+            Err(error) => {
+                skipped.push((relative, format!("not readable: {}", error)));
+                continue;
+            }
+        };
+        if metadata.is_dir() {
+            // a directory symlinked from inside the tree is copied once, and loops end
+            let canonical = std::fs::canonicalize(&path).unwrap_or(path.clone());
+            if visited.insert(canonical) {
+                find_copy_candidates(&path, root, &format!("{}/", relative), rules, visited, candidates, skipped);
+            } else {
+                skipped.push((format!("{}/", relative), "already copied (symlinked directory)".to_string()));
+            }
+            continue;
+        }
+        if !metadata.is_file() {
+            skipped.push((relative, "not a regular file".to_string()));
+            continue;
+        }
+        // patterns with a '/' match the path, otherwise the file name
+        let matches = |pattern: &String| glob_match(pattern.as_bytes(), if pattern.contains('/') { relative.as_bytes() } else { name.as_bytes() });
+        if !rules.include.is_empty() && !rules.include.iter().any(matches) {
+            skipped.push((relative, "not included".to_string()));
+            continue;
+        }
+        if let Some(pattern) = rules.exclude.iter().find(|pattern| matches(pattern)) {
+            skipped.push((relative, format!("excluded by {}", pattern)));
+            continue;
+        }
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+        if let Some(days) = rules.max_age_days {
+            if Duration::from_secs(days * 86400) < now.duration_since(modified).unwrap_or_default() {
+                skipped.push((relative, format!("older than {} days", days)));
+                continue;
+            }
+        }
+        if let Err(error) = File::open(&path) {
+            skipped.push((relative, format!("not readable: {}", error)));
+            continue;
+        }
+        candidates.push(CopyCandidate { source: path, relative, size: metadata.len(), modified });
+    }
+}
+
+/** Copies the file as it was when listed (size bytes), or only its last keep bytes from the start of a line, returning the bytes copied **/
+fn copy_file_tail(source: &Path, target: &Path, size: u64, keep: u64) -> io::Result<u64> {
+    let mut input = io::BufReader::new(File::open(source)?);
+    if keep < size {
+        input.seek(io::SeekFrom::Start(size - keep))?;
+        // within the first 64KiB, files without lines aren't skipped entirely
+        input.by_ref().take(65536).read_until(b'\n', &mut Vec::new())?;
+    }
+    let start = input.stream_position()?;
+    std::fs::create_dir_all(target.parent().unwrap())?;
+    let mut output = File::create(target)?;
+    io::copy(&mut input.take(size.saturating_sub(start)), &mut output)
+}
+
+/** Findings from the Cassandra (and DSE) logback logs, so the full logs/ don't have to be grepped by hand
+ *
+ * system.log* and debug.log* in logHome are parsed, newest first and within the same budgets as the copy
+ * (collectInfoLogsMaxFileMB of each, collectInfoLogsMaxTotalMB in all), into logs/summary.txt and logs/summary.json.
+ * debug.log repeats system.log's lines, each line is counted once.
+ **/
+#[derive(Default)]
+struct LogFindings {
+    // (file, lines, first timestamp, last timestamp)
+    files: Vec<(String, u64, String, String)>,
+    first: String,
+    last: String,
+    warn_error_by_logger: HashMap<(String, String), u64>,
+    gc_pauses: HashMap<String, GcPauses>,
+    // verb: (reports, messages dropped)
+    dropped_messages: HashMap<String, (u64, u64)>,
+    // table: (warnings, failures, most tombstones)
+    tombstones: HashMap<String, (u64, u64, u64)>,
+    // table: (warnings, largest bytes)
+    large_partitions: HashMap<String, (u64, f64)>,
+    // "Maximum memory usage reached": (count, first, last)
+    chunk_cache_full: (u64, String, String),
+    // peer: (DOWN, UP, last state, last transition)
+    gossip: HashMap<String, (u64, u64, String, String)>,
+    // hashes of the lines already counted
+    seen: HashSet<u64>,
+    // (file, error) of the logs that couldn't be read to the end
+    unreadable: Vec<(String, String)>,
+    // (file, why) of the logs only partly read, or not at all, because of the budgets
+    limited: Vec<(String, String)>,
+}
+
+#[derive(Default)]
+struct GcPauses {
+    count: u64,
+    total_ms: u64,
+    max_ms: u64,
+    max_at: String,
+}
+
+/** `%-5level [%thread] %date{ISO8601} %F:%L - %msg`, the logger is the source file (%F) without .java **/
+struct LogLine<'l> {
+    level: &'l str,
+    thread: &'l str,
+    timestamp: &'l str,
+    logger: &'l str,
+    message: &'l str,
+}
+
+const LOG_LEVELS: &[&str] = &["ERROR", "WARN", "INFO", "DEBUG", "TRACE"];
+
+fn parse_log_line(line: &str) -> Option<LogLine<'_>> {
+    let (level, rest) = line.split_once(' ')?;
+    let rest = rest.trim_start();
+    if !LOG_LEVELS.contains(&level) || !rest.starts_with('[') {
+        return None;
+    }
+    // thread names can contain "] ", the one closing the thread is followed by the timestamp
+    let mut from = 1;
+    loop {
+        let close = rest[from..].find("] ")? + from;
+        let after = &rest[close + 2..];
+        if let Some(timestamp) = after.get(..23).filter(|timestamp| is_log_timestamp(timestamp)) {
+            let (source, message) = after[23..].trim_start().split_once(" - ")?;
+            let logger = source.split(':').next().unwrap_or(source).trim_end_matches(".java");
+            return Some(LogLine { level, thread: &rest[1..close], timestamp, logger, message });
+        }
+        from = close + 1;
+    }
+}
+
+/** `2024-01-31 23:59:59,999`, which sorts as text **/
+fn is_log_timestamp(timestamp: &str) -> bool {
+    timestamp.bytes().enumerate().all(|(i, byte)| match i {
+        4 | 7 => b'-' == byte,
+        10 => b' ' == byte || b'T' == byte,
+        13 | 16 => b':' == byte,
+        19 => b',' == byte || b'.' == byte,
+        _ => byte.is_ascii_digit(),
+    })
+}
+
+fn native_log_summary(options: &Options) -> io::Result<Vec<(String, String)>> {
+    if !options.log_summary {
+        return Ok(Vec::new());
+    }
+    let mut findings = LogFindings::default();
+    let budget = |megabytes: u64| if 0 == megabytes { u64::MAX } else { megabytes * MIB };
+    let max_file_bytes = budget(options.logs_max_file_mb);
+    let mut remaining = budget(options.logs_max_total_mb);
+    let mut paths: Vec<(SystemTime, PathBuf)> = cassandra_log_files(options.log_home)?
+        .into_iter()
+        .map(|path| (std::fs::metadata(&path).and_then(|metadata| metadata.modified()).unwrap_or(UNIX_EPOCH), path))
+        .collect();
+    paths.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for (_, path) in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if 0 == remaining {
+            findings.limited.push((name, format!("not read, beyond collectInfoLogsMaxTotalMB ({} MiB)", options.logs_max_total_mb)));
+            continue;
+        }
+        let limit = max_file_bytes.min(remaining);
+        let mut read: u64 = 0;
+        let size = std::fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+        let compressed = name.ends_with(".gz") || name.ends_with(".zip");
+        // a corrupt rotated log doesn't stop the others
+        let result = if compressed {
+            // compressed logs can't be read from the end, so the start of each is read
+            for_each_log(&path, &mut |name, reader| {
+                let mut limited = reader.take(limit.saturating_sub(read));
+                read += analyse_log(name, &mut limited, &mut findings)?;
+                Ok(())
+            })
+        } else {
+            // the partial line skipped before the tail counts too
+            analyse_log_tail(&path, limit, &mut findings).map(|_| read = size.min(limit))
+        };
+        if let Err(error) = result {
+            findings.unreadable.push((name.clone(), error.to_string()));
+        }
+        if (compressed && limit <= read) || (!compressed && limit < size) {
+            findings.limited.push((name, format!("only {} read", format_mib(limit as f64))));
+        }
+        remaining = remaining.saturating_sub(read);
+    }
+    Ok(vec![
+        ("logs/summary.txt".to_string(), log_summary_text(options.log_home, &findings)),
+        ("logs/summary.json".to_string(), log_summary_json(&findings)),
+    ])
+}
+
+/** system.log* and debug.log*, including the rotated (and compressed) ones **/
+fn cassandra_log_files(log_home: &str) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(log_home)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            (name.starts_with("system.log") || name.starts_with("debug.log")) && path.is_file()
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/** Reads each log in the file: itself, the content of a .gz, or each entry of a .zip (named `file.zip:entry`), decompressed while read **/
+fn for_each_log(path: &Path, read: &mut dyn FnMut(&str, &mut dyn BufRead) -> io::Result<()>) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    if name.ends_with(".gz") {
+        return read(&name, &mut io::BufReader::new(GzipReader::new(io::BufReader::new(File::open(path)?))));
+    }
+    if name.ends_with(".zip") {
+        for entry in read_zip_directory(&mut File::open(path)?)?.iter().filter(|entry| !entry.name.ends_with('/')) {
+            read(&format!("{}:{}", name, entry.name), &mut io::BufReader::new(ZipEntryReader::open(path, entry)?))?;
+        }
+        return Ok(());
+    }
+    read(&name, &mut io::BufReader::new(File::open(path)?))
+}
+
+/** Analyses the last limit bytes of the (uncompressed) log, from the start of a line, returning the bytes read **/
+fn analyse_log_tail(path: &Path, limit: u64, findings: &mut LogFindings) -> io::Result<u64> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let mut reader = io::BufReader::new(File::open(path)?);
+    let size = reader.get_ref().metadata()?.len();
+    if limit < size {
+        reader.seek(io::SeekFrom::Start(size - limit))?;
+        reader.read_until(b'\n', &mut Vec::new())?;
+    }
+    analyse_log(&name, &mut reader, findings)
+}
+
+/** Analyses each line of the log, returning the bytes read **/
+fn analyse_log(name: &str, reader: &mut dyn BufRead, findings: &mut LogFindings) -> io::Result<u64> {
+    let (mut lines, mut first, mut last) = (0, String::new(), String::new());
+    let mut bytes: u64 = 0;
+    let mut buffer: Vec<u8> = Vec::new();
+    while 0 < reader.read_until(b'\n', &mut buffer)? {
+        bytes += buffer.len() as u64;
+        {
+            let line = String::from_utf8_lossy(&buffer);
+            // lines that don't parse continue the previous one, eg stack traces
+            if let Some(parsed) = parse_log_line(line.trim_end()) {
+                lines += 1;
+                if first.is_empty() {
+                    first = parsed.timestamp.to_string();
+                }
+                last.clear();
+                last.push_str(parsed.timestamp);
+                analyse_log_line(&parsed, findings);
+            }
+        }
+        buffer.clear();
+    }
+    if findings.first.is_empty() || (!first.is_empty() && first < findings.first) {
+        findings.first = first.clone();
+    }
+    if findings.last < last {
+        findings.last = last.clone();
+    }
+    findings.files.push((name.to_string(), lines, first, last));
+    Ok(bytes)
+}
+
+fn analyse_log_line(line: &LogLine, findings: &mut LogFindings) {
+    let warn_error = "WARN" == line.level || "ERROR" == line.level;
+    let message = line.message;
+    let relevant = warn_error
+        || message.contains(" GC in ")
+        || message.contains(" is now ")
+        || message.contains(" messages were dropped in last ")
+        || message.contains("large partition ")
+        || message.contains("Maximum memory usage reached");
+    if !relevant {
+        return;
+    }
+    // counted once, when in both system.log and debug.log
+    let mut hasher = DefaultHasher::new();
+    (line.timestamp, line.thread, message).hash(&mut hasher);
+    if !findings.seen.insert(hasher.finish()) {
+        return;
+    }
+    if warn_error {
+        *findings.warn_error_by_logger.entry((line.level.to_string(), line.logger.to_string())).or_insert(0) += 1;
+    }
+
+    // G1 Young Generation GC in 233ms.  G1 Eden Space: …
+    if let Some(at) = message.find(" GC in ").filter(|_| "GCInspector" == line.logger) {
+        let milliseconds: String = message[at + 7..].chars().take_while(|c| c.is_ascii_digit()).collect();
+        if let Ok(milliseconds) = u64::from_str(&milliseconds) {
+            let pauses = findings.gc_pauses.entry(message[..at].trim().to_string()).or_default();
+            pauses.count += 1;
+            pauses.total_ms += milliseconds;
+            if pauses.max_ms < milliseconds {
+                pauses.max_ms = milliseconds;
+                pauses.max_at = line.timestamp.to_string();
+            }
+        }
+    }
+    // MUTATION messages were dropped in last 5000 ms: 0 internal and 12 cross node. Mean internal dropped latency: …
+    if let Some(at) = message.find(" messages were dropped in last ") {
+        let counts = message[at..].split_once(": ").map(|(_, counts)| counts.split('.').next().unwrap_or("")).unwrap_or("");
+        let dropped: u64 = counts.split(|c: char| !c.is_ascii_digit()).filter_map(|number| u64::from_str(number).ok()).sum();
+        let verb = message[..at].rsplit(' ').next().unwrap_or("").to_string();
+        let entry = findings.dropped_messages.entry(verb).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += dropped;
+    }
+    // Read 10 live rows and 1001 tombstone cells for query SELECT * FROM ks.table WHERE … (see tombstone_warn_threshold)
+    // Scanned over 100001 tombstones during query 'SELECT * FROM ks.table …' …; query aborted
+    let tombstones = if let Some(at) = message.find(" tombstone cells for query ") {
+        message[..at].rsplit(' ').next().and_then(|count| u64::from_str(count).ok()).map(|count| (count, false))
+    } else if let Some(scanned) = message.strip_prefix("Scanned over ") {
+        scanned.split(' ').next().and_then(|count| u64::from_str(count).ok()).map(|count| (count, true))
+    } else {
+        None
+    };
+    if let Some((count, failure)) = tombstones {
+        let table = message
+            .split_once(" FROM ")
+            .and_then(|(_, from)| from.split_whitespace().next())
+            .map(|table| table.trim_matches(|c: char| '"' == c || '\'' == c || ';' == c).to_string())
+            .unwrap_or("unknown".to_string());
+        let entry = findings.tombstones.entry(table).or_insert((0, 0, 0));
+        if failure {
+            entry.1 += 1;
+        } else {
+            entry.0 += 1;
+        }
+        entry.2 = entry.2.max(count);
+    }
+    // Writing large partition ks/table:key (123.456MiB) to sstable …, or Compacting large partition ks/table:key (123456789 bytes)
+    if let Some((_, partition)) = message.split_once("large partition ") {
+        let table = partition.split(':').next().unwrap_or("").replace('/', ".");
+        let bytes = partition.split_once(" (").and_then(|(_, size)| size.split(')').next()).and_then(parse_log_size).unwrap_or(0.0);
+        let entry = findings.large_partitions.entry(table).or_insert((0, 0.0));
+        entry.0 += 1;
+        entry.1 = entry.1.max(bytes);
+    }
+    if message.contains("Maximum memory usage reached") {
+        let chunk_cache = &mut findings.chunk_cache_full;
+        chunk_cache.0 += 1;
+        if chunk_cache.1.is_empty() || line.timestamp < chunk_cache.1.as_str() {
+            chunk_cache.1 = line.timestamp.to_string();
+        }
+        if chunk_cache.2.as_str() < line.timestamp {
+            chunk_cache.2 = line.timestamp.to_string();
+        }
+    }
+    // InetAddress /10.0.0.2:7000 is now DOWN
+    if let Some((before, state)) = message.split_once(" is now ") {
+        if let Some(peer) = before.strip_prefix("InetAddress ") {
+            let entry = findings.gossip.entry(peer.trim_start_matches('/').to_string()).or_insert((0, 0, String::new(), String::new()));
+            match state.trim() {
+                "DOWN" => entry.0 += 1,
+                "UP" => entry.1 += 1,
+                _ => return,
+            }
+            if entry.3.as_str() <= line.timestamp {
+                entry.2 = state.trim().to_string();
+                entry.3 = line.timestamp.to_string();
+            }
+        }
+    }
+}
+
+/** `123.456MiB`, `1.2 GiB` or `123456789 bytes` as bytes **/
+fn parse_log_size(size: &str) -> Option<f64> {
+    let size = size.trim();
+    let number_end = size.find(|c: char| !c.is_ascii_digit() && '.' != c).unwrap_or(size.len());
+    let number = f64::from_str(&size[..number_end]).ok()?;
+    let multiplier = match size[number_end..].trim().to_ascii_lowercase().as_str() {
+        "" | "b" | "bytes" => 1.0,
+        "kib" | "kb" => 1024.0,
+        "mib" | "mb" => 1024.0 * 1024.0,
+        "gib" | "gb" => 1024.0 * 1024.0 * 1024.0,
+        "tib" | "tb" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some(number * multiplier)
+}
+
+fn format_mib(bytes: f64) -> String {
+    format!("{:.1} MiB", bytes / (1024.0 * 1024.0))
+}
+
+/** Largest count first, then by key **/
+fn sorted_findings<K: Ord + Clone, V, F: Fn(&V) -> u64>(findings: &HashMap<K, V>, count: F) -> Vec<(&K, &V)> {
+    let mut sorted: Vec<(&K, &V)> = findings.iter().collect();
+    sorted.sort_by(|a, b| count(b.1).cmp(&count(a.1)).then(a.0.cmp(b.0)));
+    sorted
+}
+
+fn log_summary_text(log_home: &str, findings: &LogFindings) -> String {
+    let mut text = format!("Cassandra log summary of {} (system.log*, debug.log*)\n", log_home);
+    text += &format!("covering {} to {}\n", or_none(&findings.first), or_none(&findings.last));
+    text += "\nfiles:\n";
+    for (file, lines, first, last) in findings.files.iter() {
+        text += &format!("  {:<32} {:>10} lines  {} to {}\n", file, lines, or_none(first), or_none(last));
+    }
+    for (file, error) in findings.unreadable.iter() {
+        text += &format!("  {:<32} unreadable: {}\n", file, error);
+    }
+    for (file, why) in findings.limited.iter() {
+        text += &format!("  {:<32} {}\n", file, why);
+    }
+    text += "\nWARN and ERROR by logger:\n";
+    for ((level, logger), count) in sorted_findings(&findings.warn_error_by_logger, |count| *count) {
+        text += &format!("  {:<5} {:>8}  {}\n", level, count, logger);
+    }
+    text += "\nGC pauses (GCInspector):\n";
+    for (collector, pauses) in sorted_findings(&findings.gc_pauses, |pauses| pauses.count) {
+        text += &format!(
+            "  {}: {} pauses, total {} ms, mean {} ms, max {} ms at {}\n",
+            collector,
+            pauses.count,
+            pauses.total_ms,
+            pauses.total_ms / pauses.count,
+            pauses.max_ms,
+            pauses.max_at
+        );
+    }
+    text += "\ndropped messages:\n";
+    for (verb, (reports, dropped)) in sorted_findings(&findings.dropped_messages, |counts| counts.1) {
+        text += &format!("  {}: {} dropped, in {} reports\n", verb, dropped, reports);
+    }
+    text += "\ntombstone warnings (tombstone_warn_threshold) and failures (tombstone_failure_threshold):\n";
+    for (table, (warnings, failures, most)) in sorted_findings(&findings.tombstones, |counts| counts.0 + counts.1) {
+        text += &format!("  {}: {} warnings, {} failures, at most {} tombstones\n", table, warnings, failures, most);
+    }
+    text += "\nlarge partitions:\n";
+    for (table, (warnings, largest)) in sorted_findings(&findings.large_partitions, |counts| counts.0) {
+        text += &format!("  {}: {} warnings, largest {}\n", table, warnings, format_mib(*largest));
+    }
+    let (count, first, last) = &findings.chunk_cache_full;
+    text += &format!("\nchunk cache full (\"Maximum memory usage reached\"): {} times", count);
+    text += &if 0 < *count { format!(", {} to {}\n", first, last) } else { "\n".to_string() };
+    text += "\ngossip (peers going DOWN and UP):\n";
+    for (peer, (down, up, state, at)) in sorted_findings(&findings.gossip, |counts| counts.0 + counts.1) {
+        text += &format!("  {}: {} DOWN, {} UP, last {} at {}\n", peer, down, up, state, at);
+    }
+    text
+}
+
+fn or_none(timestamp: &str) -> &str {
+    if timestamp.is_empty() {
+        "none"
+    } else {
+        timestamp
+    }
+}
+
+fn log_summary_json(findings: &LogFindings) -> String {
+    let optional = |timestamp: &str| if timestamp.is_empty() { None } else { Some(timestamp.to_string()) };
+    let files: Vec<String> = findings
+        .files
+        .iter()
+        .map(|(file, lines, first, last)| {
+            JsonObject::new().string("file", file).number("lines", lines).string_or_null("first", optional(first).as_deref()).string_or_null("last", optional(last).as_deref()).build()
+        })
+        .collect();
+    let warn_error: Vec<String> = sorted_findings(&findings.warn_error_by_logger, |count| *count)
+        .iter()
+        .map(|((level, logger), count)| JsonObject::new().string("level", level).string("logger", logger).number("count", count).build())
+        .collect();
+    let gc_pauses: Vec<String> = sorted_findings(&findings.gc_pauses, |pauses| pauses.count)
+        .iter()
+        .map(|(collector, pauses)| {
+            JsonObject::new()
+                .string("collector", collector)
+                .number("count", pauses.count)
+                .number("total_ms", pauses.total_ms)
+                .number("mean_ms", pauses.total_ms / pauses.count)
+                .number("max_ms", pauses.max_ms)
+                .string("max_at", &pauses.max_at)
+                .build()
+        })
+        .collect();
+    let dropped: Vec<String> = sorted_findings(&findings.dropped_messages, |counts| counts.1)
+        .iter()
+        .map(|(verb, (reports, dropped))| JsonObject::new().string("verb", verb).number("reports", reports).number("dropped", dropped).build())
+        .collect();
+    let tombstones: Vec<String> = sorted_findings(&findings.tombstones, |counts| counts.0 + counts.1)
+        .iter()
+        .map(|(table, (warnings, failures, most))| JsonObject::new().string("table", table).number("warnings", warnings).number("failures", failures).number("max_tombstones", most).build())
+        .collect();
+    let large_partitions: Vec<String> = sorted_findings(&findings.large_partitions, |counts| counts.0)
+        .iter()
+        .map(|(table, (warnings, largest))| JsonObject::new().string("table", table).number("warnings", warnings).number("max_bytes", largest.round() as u64).build())
+        .collect();
+    let gossip: Vec<String> = sorted_findings(&findings.gossip, |counts| counts.0 + counts.1)
+        .iter()
+        .map(|(peer, (down, up, state, at))| JsonObject::new().string("peer", peer).number("down", down).number("up", up).string("last_state", state).string("last_at", at).build())
+        .collect();
+    let (count, first, last) = &findings.chunk_cache_full;
+    JsonObject::new()
+        .string_or_null("first", optional(&findings.first).as_deref())
+        .string_or_null("last", optional(&findings.last).as_deref())
+        .raw("files", &json_array(&files))
+        .raw("unreadable", &json_array(&findings.unreadable.iter().map(|(file, error)| JsonObject::new().string("file", file).string("error", error).build()).collect::<Vec<String>>()))
+        .raw("limited", &json_array(&findings.limited.iter().map(|(file, why)| JsonObject::new().string("file", file).string("why", why).build()).collect::<Vec<String>>()))
+        .raw("warn_error_by_logger", &json_array(&warn_error))
+        .raw("gc_pauses", &json_array(&gc_pauses))
+        .raw("dropped_messages", &json_array(&dropped))
+        .raw("tombstones", &json_array(&tombstones))
+        .raw("large_partitions", &json_array(&large_partitions))
+        .raw("chunk_cache_full", &JsonObject::new().number("count", count).string_or_null("first", optional(first).as_deref()).string_or_null("last", optional(last).as_deref()).build())
+        .raw("gossip", &json_array(&gossip))
+        .build()
+}
+
+/** GC pauses and notable events from the JVM GC logs (gc*.log* in logHome, rotated and compressed ones included) into logs/gc-summary.txt
+ *
+ * Reads both the legacy -XX:+PrintGCDetails format (with or without -XX:+PrintGCDateStamps) and the JDK 9+ unified logging (-Xlog:gc*),
+ * for G1, CMS, Parallel and ZGC. Pauses are timed by the wall clock when logged, otherwise by the JVM uptime.
+ **/
+#[derive(Default)]
+struct GcFindings {
+    // (file, pauses)
+    files: Vec<(String, usize)>,
+    unreadable: Vec<(String, String)>,
+    collectors: Vec<&'static str>,
+    pauses: Vec<GcPause>,
+    humongous_allocations: u64,
+    evacuation_failures: u64,
+    concurrent_mode_failures: u64,
+    allocation_stalls: u64,
+}
+
+struct GcPause {
+    kind: &'static str,
+    milliseconds: f64,
+    // the wall clock (as logged) or JVM uptime
+    at: String,
+    // the hour it is accounted to, sorting as text
+    hour: String,
+}
+
+fn native_gc_summary(options: &Options) -> io::Result<Vec<(String, String)>> {
+    if !options.gc_summary {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(options.log_home)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            name.starts_with("gc") && name.contains(".log") && path.is_file()
+        })
+        .collect();
+    files.sort();
+    let mut findings = GcFindings::default();
+    for path in files.iter() {
+        if let Err(error) = for_each_log(path, &mut |name, reader| analyse_gc_log(name, reader, &mut findings)) {
+            findings.unreadable.push((path.file_name().unwrap_or_default().to_string_lossy().to_string(), error.to_string()));
+        }
+    }
+    Ok(vec![("logs/gc-summary.txt".to_string(), gc_summary_text(options.log_home, &findings))])
+}
+
+fn analyse_gc_log(name: &str, reader: &mut dyn BufRead, findings: &mut GcFindings) -> io::Result<()> {
+    let pauses_before = findings.pauses.len();
+    // a legacy pause whose duration is on a later line (eg after -XX:+PrintTenuringDistribution's lines)
+    let mut pending: Option<(&'static str, String, String)> = None;
+    let mut buffer: Vec<u8> = Vec::new();
+    while 0 < reader.read_until(b'\n', &mut buffer)? {
+        {
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end();
+            for (marker, collector) in [("G1", "G1"), ("ParNew", "CMS"), ("CMS", "CMS"), ("PSYoungGen", "Parallel"), ("Using Parallel", "Parallel"), ("ZGC", "ZGC"), ("Z Garbage Collector", "ZGC")].iter() {
+                if line.contains(marker) && !findings.collectors.contains(collector) {
+                    findings.collectors.push(collector);
+                }
+            }
+            if line.contains("concurrent mode failure") {
+                findings.concurrent_mode_failures += 1;
+            }
+            // legacy logs without time stamps start with the event, eg [GC pause …
+            if line.starts_with('[') && !line.starts_with("[GC") && !line.starts_with("[Full GC") {
+                analyse_unified_gc_line(line, findings);
+            } else if let Some(start) = line.find("[GC ").or_else(|| line.find("[GC(")).or_else(|| line.find("[Full GC")).filter(|&start| !line[start..].starts_with("[GC concurrent")) {
+                let (wall, uptime) = legacy_gc_time(&line[..start]);
+                let (at, hour) = gc_time(wall, uptime);
+                let kind = gc_pause_kind(&line[start..]);
+                match legacy_gc_seconds(line) {
+                    Some(seconds) => record_gc_pause(findings, kind, seconds * 1000.0, at, hour, line),
+                    None => pending = Some((kind, at, hour)),
+                }
+            } else if let Some(seconds) = legacy_gc_seconds(line) {
+                if let Some((kind, at, hour)) = pending.take() {
+                    record_gc_pause(findings, kind, seconds * 1000.0, at, hour, line);
+                }
+            }
+        }
+        buffer.clear();
+    }
+    findings.files.push((name.to_string(), findings.pauses.len() - pauses_before));
+    Ok(())
+}
+
+/** `[2024-01-31T23:59:59.999+0000][12.345s][info][gc] GC(12) Pause Young (Normal) (G1 Evacuation Pause) 100M->50M(1024M) 12.345ms` **/
+fn analyse_unified_gc_line(line: &str, findings: &mut GcFindings) {
+    let (mut wall, mut uptime) = (None, None);
+    let mut rest = line;
+    while rest.starts_with('[') {
+        let close = match rest.find(']') {
+            Some(close) => close,
+            None => return,
+        };
+        let decoration = &rest[1..close];
+        if 19 <= decoration.len() && b'-' == decoration.as_bytes()[4] && b'T' == decoration.as_bytes()[10] {
+            wall = Some(decoration);
+        } else if let Some(milliseconds) = decoration.strip_suffix("ms").and_then(|number| f64::from_str(number).ok()) {
+            uptime = Some(milliseconds / 1000.0);
+        } else if let Some(seconds) = decoration.strip_suffix('s').and_then(|number| f64::from_str(number).ok()) {
+            uptime = Some(seconds);
+        }
+        rest = &rest[close + 1..];
+    }
+    // the message follows the GC id, eg GC(12)
+    let message = rest.trim_start();
+    let message = if message.starts_with("GC(") { message.split_once(") ").map(|(_, message)| message).unwrap_or(message) } else { message };
+    if message.starts_with("To-space exhausted") {
+        findings.evacuation_failures += 1;
+    }
+    if message.starts_with("Allocation Stall") {
+        findings.allocation_stalls += 1;
+    }
+    // the pause's end line, with its duration (the start line, with -Xlog:gc+start, has none)
+    if message.starts_with("Pause ") {
+        if let Some(milliseconds) = message.rsplit(' ').next().and_then(|last| last.strip_suffix("ms")).and_then(|number| f64::from_str(number).ok()) {
+            let (at, hour) = gc_time(wall, uptime);
+            record_gc_pause(findings, gc_pause_kind(message), milliseconds, at, hour, message);
+        }
+    }
+}
+
+/** The legacy prefix `2024-01-31T23:59:59.999+0000: 12.345: ` (either can be missing) **/
+fn legacy_gc_time(prefix: &str) -> (Option<&str>, Option<f64>) {
+    let (mut wall, mut uptime) = (None, None);
+    for part in prefix.split(": ").map(|part| part.trim().trim_end_matches(':')) {
+        if 19 <= part.len() && b'-' == part.as_bytes()[4] && b'T' == part.as_bytes()[10] {
+            wall = Some(part);
+        } else if let Ok(seconds) = f64::from_str(part) {
+            uptime = Some(seconds);
+        }
+    }
+    (wall, uptime)
+}
+
+/** The event's total (last) `, 0.0123456 secs]`, ignoring `[Times: … secs]` **/
+fn legacy_gc_seconds(line: &str) -> Option<f64> {
+    let line = line.split(" [Times:").next().unwrap_or(line);
+    let end = line.rfind(" secs]")?;
+    line[..end].rsplit([' ', ',']).next().and_then(|number| f64::from_str(number).ok())
+}
+
+/** (when, hour) of a pause: the wall clock `2024-01-31T23:59:59.999+0000` and its hour `2024-01-31 23:00`, or the JVM uptime **/
+fn gc_time(wall: Option<&str>, uptime: Option<f64>) -> (String, String) {
+    // a wall clock too short, or not ASCII where its date and hour should be (eg a corrupt line), is ignored
+    let wall = wall.and_then(|wall| Some((wall, wall.get(..10)?, wall.get(11..13)?)));
+    match (wall, uptime) {
+        (Some((wall, date, hour)), _) => (wall.to_string(), format!("{} {}:00", date, hour)),
+        (None, Some(uptime)) => (format!("uptime {:.3}s", uptime), format!("uptime hour {:>5}", (uptime / 3600.0) as u64)),
+        (None, None) => ("unknown".to_string(), "unknown".to_string()),
+    }
+}
+
+fn gc_pause_kind(event: &str) -> &'static str {
+    let lower = event.to_ascii_lowercase();
+    // CMS collecting the old generation within a young pause (eg a concurrent mode failure) is a full GC
+    if lower.contains("full") || lower.contains("[cms: ") {
+        "Full"
+    } else if lower.contains("remark") {
+        "Remark"
+    } else if lower.contains("cleanup") {
+        "Cleanup"
+    } else if lower.contains("mark start") {
+        "Mark Start"
+    } else if lower.contains("mark end") {
+        "Mark End"
+    } else if lower.contains("relocate start") {
+        "Relocate Start"
+    } else if lower.contains("cms initial mark") || lower.contains("pause initial mark") {
+        "Initial Mark"
+    } else if lower.contains("mixed") {
+        "Mixed"
+    } else {
+        "Young"
+    }
+}
+
+fn record_gc_pause(findings: &mut GcFindings, kind: &'static str, milliseconds: f64, at: String, hour: String, line: &str) {
+    if line.contains("Humongous Allocation") {
+        findings.humongous_allocations += 1;
+    }
+    // legacy and JDK 17+ logs note it on the pause, JDK 9 to 16 on a line of its own
+    if line.contains("to-space exhausted") || line.contains("Evacuation Failure") {
+        findings.evacuation_failures += 1;
+    }
+    findings.pauses.push(GcPause { kind, milliseconds, at, hour });
+}
+
+/** The nearest-rank percentile of sorted values **/
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((percent / 100.0 * sorted.len() as f64).ceil() as usize).max(1);
+    sorted[rank - 1]
+}
+
+fn gc_pause_statistics(pauses: &[&GcPause]) -> String {
+    let mut milliseconds: Vec<f64> = pauses.iter().map(|pause| pause.milliseconds).collect();
+    milliseconds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let longest = pauses.iter().max_by(|a, b| a.milliseconds.partial_cmp(&b.milliseconds).unwrap()).map(|pause| pause.at.as_str()).unwrap_or("");
+    format!(
+        "{} pauses, total {:.3} s, p50 {:.3} ms, p99 {:.3} ms, max {:.3} ms at {}",
+        pauses.len(),
+        milliseconds.iter().sum::<f64>() / 1000.0,
+        percentile(&milliseconds, 50.0),
+        percentile(&milliseconds, 99.0),
+        percentile(&milliseconds, 100.0),
+        longest
+    )
+}
+
+fn gc_summary_text(log_home: &str, findings: &GcFindings) -> String {
+    let mut text = format!("GC summary of {} (gc*.log*)\n", log_home);
+    let collectors = if findings.collectors.is_empty() { "unknown".to_string() } else { findings.collectors.join(", ") };
+    text += &format!("collectors: {}\n", collectors);
+    text += "\nfiles:\n";
+    for (file, pauses) in findings.files.iter() {
+        text += &format!("  {:<32} {:>10} pauses\n", file, pauses);
+    }
+    for (file, error) in findings.unreadable.iter() {
+        text += &format!("  {:<32} unreadable: {}\n", file, error);
+    }
+    if findings.files.is_empty() && findings.unreadable.is_empty() {
+        text += "  none\n";
+    }
+
+    let all: Vec<&GcPause> = findings.pauses.iter().collect();
+    let mut hours: Vec<&str> = all.iter().map(|pause| pause.hour.as_str()).collect();
+    hours.sort();
+    hours.dedup();
+    text += &format!("\npauses: {}\n", if all.is_empty() { "none".to_string() } else { gc_pause_statistics(&all) });
+    // logs without the wall clock have only the JVM uptime
+    let mut wall: Vec<&str> = all.iter().map(|pause| pause.at.as_str()).filter(|at| at.starts_with(|c: char| c.is_ascii_digit())).collect();
+    wall.sort();
+    if let (Some(first), Some(last)) = (wall.first(), wall.last()) {
+        text += &format!("covering {} to {}\n", first, last);
+    }
+    text += "\nby kind:\n";
+    let mut kinds: Vec<&str> = all.iter().map(|pause| pause.kind).collect();
+    kinds.sort();
+    kinds.dedup();
+    for kind in kinds {
+        let pauses: Vec<&GcPause> = all.iter().filter(|pause| kind == pause.kind).cloned().collect();
+        text += &format!("  {:<14} {}\n", kind, gc_pause_statistics(&pauses));
+    }
+    text += "\npause time per hour (the share of the hour the application was paused):\n";
+    for hour in hours {
+        let pauses: Vec<&GcPause> = all.iter().filter(|pause| hour == pause.hour).cloned().collect();
+        let total: f64 = pauses.iter().map(|pause| pause.milliseconds).sum();
+        text += &format!("  {}  {:>6} pauses  {:>10.1} ms  {:>7.3}%\n", hour, pauses.len(), total, total / 36_000.0);
+    }
+    text += "\nevents:\n";
+    text += &format!("  full GCs: {}\n", all.iter().filter(|pause| "Full" == pause.kind).count());
+    text += &format!("  humongous allocations (G1): {}\n", findings.humongous_allocations);
+    text += &format!("  to-space exhausted / evacuation failures (G1): {}\n", findings.evacuation_failures);
+    text += &format!("  concurrent mode failures (CMS): {}\n", findings.concurrent_mode_failures);
+    text += &format!("  allocation stalls (ZGC): {}\n", findings.allocation_stalls);
+    text
+}
+
+/** The incident window of the logs-window/ extraction, in seconds of the logs' clock (their timestamps read as UTC)
+ *
+ * Either the last collectInfoLogsWindowHours hours, or from collectInfoLogsWindowStart to collectInfoLogsWindowEnd (either can be left open),
+ * given as `YYYY-MM-DD HH:MM[:SS]` in the logs' time zone.
+ **/
+struct LogWindow {
+    start: Option<i64>,
+    end: Option<i64>,
+    // the logs' clock minus UTC
+    clock_offset: i64,
+    description: String,
+}
+
+impl LogWindow {
+    fn contains(&self, time: i64) -> bool {
+        self.start.is_none_or(|start| start <= time) && self.end.is_none_or(|end| time <= end)
+    }
+}
+
+fn log_window(options: &Options) -> io::Result<Option<LogWindow>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let (clock_offset, clock) = log_clock_offset(options.log_home);
+    let hours = options.logs_window_hours.trim();
+    if !hours.is_empty() {
+        let hours = u64::from_str(hours).map_err(|_| invalid(format!("collectInfoLogsWindowHours=\"{}\" is not a whole number of hours", hours)))?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64 + clock_offset;
+        let description = format!("the last {} hours, {}", hours, clock);
+        return Ok(Some(LogWindow { start: Some(now - hours as i64 * 3600), end: Some(now), clock_offset, description }));
+    }
+    let bound = |name: &str, value: &str| match value.trim() {
+        "" => Ok(None),
+        value => log_timestamp_seconds(value).map(Some).ok_or_else(|| invalid(format!("{}=\"{}\" is not YYYY-MM-DD HH:MM[:SS]", name, value))),
+    };
+    let start = bound("collectInfoLogsWindowStart", options.logs_window_start)?;
+    let end = bound("collectInfoLogsWindowEnd", options.logs_window_end)?;
+    if start.is_none() && end.is_none() {
+        return Ok(None);
+    }
+    let description = format!(
+        "{} to {}, in the logs' time zone",
+        if start.is_some() { options.logs_window_start.trim() } else { "the first line" },
+        if end.is_some() { options.logs_window_end.trim() } else { "the last line" }
+    );
+    Ok(Some(LogWindow { start, end, clock_offset, description }))
+}
+
+/** `YYYY-MM-DD HH:MM[:SS[,mmm]]` as seconds since the epoch, ignoring the milliseconds **/
+fn log_timestamp_seconds(timestamp: &str) -> Option<i64> {
+    let bytes = timestamp.as_bytes();
+    let full = if 19 <= bytes.len() && b':' == bytes[16] { 19 } else { 16 };
+    let shape = bytes.get(..full)?.iter().enumerate().all(|(i, byte)| match i {
+        4 | 7 => b'-' == *byte,
+        10 => b' ' == *byte || b'T' == *byte,
+        13 | 16 => b':' == *byte,
+        _ => byte.is_ascii_digit(),
+    });
+    if !shape || bytes[full..].first().is_some_and(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let number = |from: usize, to: usize| i64::from_str(&timestamp[from..to]).unwrap_or(0);
+    let (month, day, hour, minute) = (number(5, 7), number(8, 10), number(11, 13), number(14, 16));
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || 23 < hour || 59 < minute {
+        return None;
+    }
+    let second = if 19 == full { number(17, 19) } else { 0 };
+    Some(days_from_civil(number(0, 4), month as u32, day as u32) * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/** The logs' clock minus UTC, rounded to a quarter hour, from system.log's last line having been written when it was last modified **/
+fn log_clock_offset(log_home: &str) -> (i64, String) {
+    let path = Path::new(log_home).join("system.log");
+    let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok().and_then(|modified| modified.duration_since(UNIX_EPOCH).ok());
+    match (last_log_timestamp(&path), modified) {
+        (Some(last), Some(modified)) => {
+            let offset = ((last - modified.as_secs() as i64) as f64 / 900.0).round() as i64 * 900;
+            let sign = if offset < 0 { '-' } else { '+' };
+            (offset, format!("the logs' clock being UTC{}{:02}:{:02} (from system.log's last line and modification time)", sign, offset.abs() / 3600, offset.abs() % 3600 / 60))
+        }
+        _ => (0, "the logs' clock assumed to be UTC (system.log has no timestamped line)".to_string()),
+    }
+}
+
+/** The last timestamp in the file's last 64KiB **/
+fn last_log_timestamp(path: &Path) -> Option<i64> {
+    let mut file = File::open(path).ok()?;
+    let size = file.metadata().ok()?.len();
+    file.seek(io::SeekFrom::Start(size.saturating_sub(65536))).ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+    String::from_utf8_lossy(&tail).lines().rev().find_map(|line| parse_log_line(line).and_then(|parsed| log_timestamp_seconds(parsed.timestamp)))
+}
+
+/** Incident window extraction: the lines of system.log* and debug.log* within the window, with the stack traces of those lines,
+ * into logs-window/ (and logs-window/WINDOW-REPORT.txt), alongside their logs/ copy or instead of it (collectInfoLogsWindowOnly)
+ **/
+fn native_logs_window(options: &Options) -> io::Result<Vec<(String, String)>> {
+    let window = match log_window(options)? {
+        Some(window) => window,
+        None => return Ok(Vec::new()),
+    };
+    let target = Path::new(options.artifact_dir).join("logs-window");
+    let mut report = format!("# lines of {} within {}\n", options.log_home, window.description);
+    report += "\n# lines extracted, file\n";
+    let (mut extracted, mut files) = (0, 0);
+    for path in cassandra_log_files(options.log_home)? {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        // the last line was written when the file was last modified, an hour's margin for the clock offset
+        let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok().and_then(|modified| modified.duration_since(UNIX_EPOCH).ok());
+        let ended = modified.map(|modified| modified.as_secs() as i64 + window.clock_offset + 3600);
+        if let (Some(start), Some(ended)) = (window.start, ended) {
+            if ended < start {
+                report += &format!("{:>12}  {}  (ended before the window)\n", 0, name);
+                continue;
+            }
+        }
+        let result = for_each_log(&path, &mut |log, reader| {
+            // logs-window/system.log.1 for system.log.1.gz, or for the system.log.1 entry of system.log.1.zip
+            let target_name = log.rsplit(':').next().unwrap_or(log).trim_end_matches(".gz");
+            let lines = extract_log_window(reader, &target.join(target_name), &window)?;
+            report += &format!("{:>12}  {}\n", lines, log);
+            extracted += lines;
+            if 0 < lines {
+                files += 1;
+            }
+            Ok(())
+        });
+        if let Err(error) = result {
+            report += &format!("{:>12}  {}  (failed: {})\n", 0, name, error);
+        }
+    }
+    report += &format!("\n{} lines extracted into {} files\n", extracted, files);
+    Ok(vec![("logs-window/WINDOW-REPORT.txt".to_string(), report)])
+}
+
+/** Copies the lines within the window, creating the target only when there are some, and returns how many **/
+fn extract_log_window(reader: &mut dyn BufRead, target: &Path, window: &LogWindow) -> io::Result<u64> {
+    let mut output: Option<io::BufWriter<File>> = None;
+    let (mut in_window, mut extracted) = (false, 0);
+    let mut line: Vec<u8> = Vec::new();
+    while 0 < reader.read_until(b'\n', &mut line)? {
+        let text = String::from_utf8_lossy(&line);
+        if let Some(time) = parse_log_line(text.trim_end()).and_then(|parsed| log_timestamp_seconds(parsed.timestamp)) {
+            // lines are written roughly in order, a minute later is past the window
+            if window.end.is_some_and(|end| end + 60 < time) {
+                break;
+            }
+            in_window = window.contains(time);
+        }
+        // lines that don't parse (stack traces) belong to the line before
+        if in_window {
+            if output.is_none() {
+                std::fs::create_dir_all(target.parent().unwrap())?;
+                output = Some(io::BufWriter::new(File::create(target)?));
+            }
+            output.as_mut().unwrap().write_all(&line)?;
+            extracted += 1;
+        }
+        line.clear();
+    }
+    if let Some(mut output) = output {
+        output.flush()?;
+    }
+    Ok(extracted)
+}
+
+/** Resolves each Cassandra directory to its mount and backing block devices,
+ * writing storage/topology.json and the table storage/topology.txt
+ * Each instance's directories and mounts are those of its own process (options.cassandra_pid).
+ **/
+fn native_storage_topology(options: &Options) -> io::Result<Vec<(String, String)>> {
+    let mounts = read_mountinfo(options.cassandra_pid)?;
+    let mut json_directories: Vec<String> = Vec::new();
+    let mut table = format!("pid {}\n\n", if options.cassandra_pid.is_empty() { "none" } else { options.cassandra_pid });
+    table.push_str(&format!("{:<13} {:<40} {:<24} {:<8} {:<28} {}\n", "role", "directory", "mount point", "fs", "source", "devices"));
+    let mut devices_described: Vec<BlockDevice> = Vec::new();
+
+    for (role, dir) in cassandra_directories(options) {
+        let resolved = std::fs::canonicalize(&dir).ok();
+        let mut json = JsonObject::new();
+        json.string("role", &role).string("path", &dir).boolean("exists", resolved.is_some());
+        json.string_or_null("resolved_path", resolved.as_ref().map(|path| path.to_string_lossy().to_string()).as_deref());
+        let mount = resolved.as_ref().and_then(|path| find_mount(&mounts, path));
+        let mut chain: Vec<BlockDevice> = Vec::new();
+        if let Some(mount) = mount {
+            json.string("mount_point", &mount.mount_point)
+                .string("fs_type", &mount.fs_type)
+                .string("source", &mount.source)
+                .string("mount_options", &mount.options)
+                .string("super_options", &mount.super_options)
+                .string("device_number", &mount.device_number);
+            // virtual filesystems (eg overlay, tmpfs) have no block device
+            if let Some(name) = block_device_name(&mount.device_number) {
+                block_device_chain(&name, &mut chain);
+            }
+        }
+        let chain_names: Vec<String> = chain
+            .iter()
+            .map(|device| if device.dm_name.is_empty() { device.name.clone() } else { format!("{}[{}]", device.name, device.dm_name) })
+            .collect();
+        let device_names: Vec<&str> = chain.iter().map(|device| device.name.as_str()).collect();
+        json.raw("devices", &json_string_array(&device_names));
+        json_directories.push(json.build());
+        table.push_str(&format!(
+            "{:<13} {:<40} {:<24} {:<8} {:<28} {}\n",
+            role, dir,
+            mount.map(|mount| mount.mount_point.as_str()).unwrap_or(if resolved.is_some() { "?" } else { "(not found)" }),
+            mount.map(|mount| mount.fs_type.as_str()).unwrap_or(""),
+            mount.map(|mount| mount.source.as_str()).unwrap_or(""),
+            chain_names.join(" > ")
+        ));
+        for device in chain {
+            if !devices_described.iter().any(|described| described.name == device.name) {
+                devices_described.push(device);
+            }
+        }
+    }
+
+    table.push_str(&format!("\n{:<12} {:<10} {:<16} {:>14}", "device", "kind", "dm name", "size bytes"));
+    QUEUE_PARAMETERS.iter().for_each(|parameter| table.push_str(&format!(" {:>19}", parameter)));
+    table.push_str("  slaves\n");
+    let mut json_devices: Vec<String> = Vec::new();
+    for device in devices_described.iter() {
+        table.push_str(&format!("{:<12} {:<10} {:<16} {:>14}", device.name, device.kind, device.dm_name, device.size_bytes));
+        let mut queue = JsonObject::new();
+        for (parameter, value) in device.queue.iter() {
+            table.push_str(&format!(" {:>19}", value));
+            queue.string_or_null(parameter, if value.is_empty() { None } else { Some(value) });
+        }
+        let slaves: Vec<&str> = device.slaves.iter().map(|slave| slave.as_str()).collect();
+        table.push_str(&format!("  {}\n", slaves.join(",")));
+        json_devices.push(
+            JsonObject::new()
+                .string("name", &device.name)
+                .string("kind", device.kind)
+                .string_or_null("dm_name", if device.dm_name.is_empty() { None } else { Some(&device.dm_name) })
+                .number("size_bytes", device.size_bytes)
+                .raw("queue", &queue.build())
+                .raw("slaves", &json_string_array(&slaves))
+                .build(),
+        );
+    }
+
+    let topology = JsonObject::new()
+        .string_or_null("pid", if options.cassandra_pid.is_empty() { None } else { Some(options.cassandra_pid) })
+        .raw("directories", &json_array(&json_directories))
+        .raw("devices", &json_array(&json_devices))
+        .build();
+    Ok(vec![("storage/topology.json".to_string(), format!("{}\n", topology)), ("storage/topology.txt".to_string(), table)])
+}
+
+// the commands the sampler replaces
+const SAMPLER_REPLACES: &[&str] = &["os.vmstat", "os.top", "storage.iostat-dmx", "storage.dstat"];
+
+// like the commands it replaces, the sampler is skipped with skipStat
+fn sampling_os_metrics(options: &Options) -> bool {
+    options.native_sampler && 0 < options.sample_duration && !bool::from_str(&env::var("skipStat").unwrap_or("false".to_string())).unwrap_or(false)
+}
+
+/** One reading of the /proc counters, the rates are the differences between two samples **/
+struct Sample {
+    at: Instant,
+    time: SystemTime,
+    // cpu lines of /proc/stat: user nice system idle iowait irq softirq steal (in ticks)
+    cpus: Vec<(String, Vec<u64>)>,
+    // other lines of /proc/stat, eg ctxt intr procs_running procs_blocked
+    stat: HashMap<String, u64>,
+    meminfo: HashMap<String, u64>,
+    vmstat: HashMap<String, u64>,
+    // fields of /proc/diskstats after the device name
+    disks: Vec<(String, Vec<u64>)>,
+    // fields of /proc/net/dev after the interface name
+    nics: Vec<(String, Vec<u64>)>,
+    process: Option<ProcessSample>,
+}
+
+struct ProcessSample {
+    state: String,
+    threads: u64,
+    rss_kb: u64,
+    // utime + stime in ticks
+    cpu_ticks: u64,
+    minor_faults: u64,
+    major_faults: u64,
+    // /proc/<pid>/io is only readable by the process's user (or root)
+    io: Option<(u64, u64)>,
+}
+
+// ticks per second of /proc/stat and /proc/<pid>/stat, USER_HZ is 100 on all Linux platforms Cassandra runs on
+const USER_HZ: f64 = 100.0;
+
+// (name, values) of lines starting with a name, eg `cpu0 1 2 3` or `  eth0: 1 2 3`
+fn read_named_counters(path: &str, skip: usize) -> io::Result<Vec<(String, Vec<u64>)>> {
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .map(|line| line.replacen(':', " ", 1))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(skip);
+            let name = fields.next()?.to_string();
+            Some((name, fields.filter_map(|field| u64::from_str(field).ok()).collect()))
+        })
+        .collect())
+}
+
+fn take_sample(cassandra_pid: &str) -> io::Result<Sample> {
+    let stat = read_named_counters("/proc/stat", 0)?;
+    let vmstat = read_named_counters("/proc/vmstat", 0).unwrap_or_default();
+    Ok(Sample {
+        at: Instant::now(),
+        time: SystemTime::now(),
+        cpus: stat.iter().filter(|line| line.0.starts_with("cpu")).cloned().collect(),
+        stat: stat.iter().filter(|line| !line.0.starts_with("cpu") && !line.1.is_empty()).map(|line| (line.0.clone(), line.1[0])).collect(),
+        meminfo: read_meminfo("/proc/meminfo").unwrap_or_default(),
+        vmstat: vmstat.into_iter().filter(|line| !line.1.is_empty()).map(|line| (line.0, line.1[0])).collect(),
+        // major minor name …
+        disks: read_named_counters("/proc/diskstats", 2)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|disk| !disk.0.starts_with("loop") && !disk.0.starts_with("ram") && 11 <= disk.1.len())
+            .collect(),
+        // two header lines
+        nics: read_named_counters("/proc/net/dev", 0).unwrap_or_default().into_iter().filter(|nic| 16 <= nic.1.len()).collect(),
+        process: take_process_sample(cassandra_pid),
+    })
+}
+
+fn take_process_sample(pid: &str) -> Option<ProcessSample> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // the command name (in parentheses) can contain spaces, the fields after it start with the state
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    let field = |i: usize| fields.get(i).and_then(|field| u64::from_str(field).ok()).unwrap_or(0);
+    let status = read_meminfo(&format!("/proc/{}/status", pid)).unwrap_or_default();
+    let io = std::fs::read_to_string(format!("/proc/{}/io", pid)).ok().map(|io| {
+        let counters: HashMap<&str, u64> = io
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, ':');
+                Some((parts.next()?, u64::from_str(parts.next()?.trim()).ok()?))
+            })
+            .collect();
+        (*counters.get("read_bytes").unwrap_or(&0), *counters.get("write_bytes").unwrap_or(&0))
+    });
+    Some(ProcessSample {
+        state: fields.first()?.to_string(),
+        threads: *status.get("Threads").unwrap_or(&0),
+        rss_kb: *status.get("VmRSS").unwrap_or(&0),
+        cpu_ticks: field(11) + field(12),
+        minor_faults: field(7),
+        major_faults: field(9),
+        io,
+    })
+}
+
+/** The CSV files of os-metrics/ with their headers **/
+struct SamplerFiles {
+    cpu: io::BufWriter<File>,
+    system: io::BufWriter<File>,
+    disks: io::BufWriter<File>,
+    network: io::BufWriter<File>,
+    process: io::BufWriter<File>,
+}
+
+fn create_csv(options: &Options, file: &str, header: &str) -> Result<io::BufWriter<File>, String> {
+    let mut csv = io::BufWriter::new(create_command_output_file(options.artifact_dir, file)?);
+    writeln!(csv, "{}", header).map_err(|error| format!("failed to write {}: {}", file, error))?;
+    Ok(csv)
+}
+
+/** Takes the first sample and creates the CSV files, None (with a warning) when /proc can't be sampled **/
+fn start_sampler(options: &Options) -> Option<(Sample, SamplerFiles)> {
+    let files = || -> Result<SamplerFiles, String> {
+        Ok(SamplerFiles {
+            cpu: create_csv(options, "os-metrics/cpu.csv", "time,cpu,user_pct,nice_pct,system_pct,idle_pct,iowait_pct,irq_pct,softirq_pct,steal_pct")?,
+            system: create_csv(
+                options,
+                "os-metrics/system.csv",
+                "time,procs_running,procs_blocked,context_switches_s,interrupts_s,mem_total_kb,mem_free_kb,mem_available_kb,buffers_kb,cached_kb,dirty_kb,writeback_kb,swap_used_kb,pgpgin_kb_s,pgpgout_kb_s,pswpin_s,pswpout_s,pgfault_s,pgmajfault_s",
+            )?,
+            disks: create_csv(
+                options,
+                "os-metrics/disks.csv",
+                "time,device,reads_s,writes_s,read_kb_s,write_kb_s,reads_merged_s,writes_merged_s,r_await_ms,w_await_ms,queue_size,util_pct",
+            )?,
+            network: create_csv(
+                options,
+                "os-metrics/network.csv",
+                "time,interface,rx_kb_s,tx_kb_s,rx_packets_s,tx_packets_s,rx_errors_s,tx_errors_s,rx_drops_s,tx_drops_s",
+            )?,
+            process: create_csv(
+                options,
+                "os-metrics/process.csv",
+                "time,pid,state,threads,rss_kb,cpu_pct,minor_faults_s,major_faults_s,read_kb_s,write_kb_s",
+            )?,
+        })
+    };
+    match take_sample(options.cassandra_pid).map_err(|error| format!("failed to read /proc/stat: {}", error)).and_then(|sample| Ok((sample, files()?))) {
+        Ok(sampler) => Some(sampler),
+        Err(error) => {
+            println!("WARNING: not sampling OS metrics natively, {}", error);
+            None
+        }
+    }
+}
+
+/** Samples every sample_interval for sample_duration seconds, writing the rates between samples into the os-metrics/ CSV files,
+ * returning the number of samples written.
+ **/
+fn sample_os_metrics(sampler: (Sample, SamplerFiles), options: &Options) -> Result<usize, String> {
+    let (mut previous, mut files) = sampler;
+    let started = previous.at;
+    let mut samples = 0;
+    while started.elapsed() < Duration::from_secs(options.sample_duration) {
+        thread::sleep(Duration::from_secs(options.sample_interval));
+        let sample = take_sample(options.cassandra_pid).map_err(|error| format!("failed to read /proc/stat: {}", error))?;
+        write_sample(&previous, &sample, options, &mut files).map_err(|error| format!("failed to write os-metrics/: {}", error))?;
+        previous = sample;
+        samples += 1;
+    }
+    for csv in [&mut files.cpu, &mut files.system, &mut files.disks, &mut files.network, &mut files.process].iter_mut() {
+        csv.flush().map_err(|error| format!("failed to write os-metrics/: {}", error))?;
+    }
+    Ok(samples)
+}
+
+fn write_sample(previous: &Sample, sample: &Sample, options: &Options, files: &mut SamplerFiles) -> io::Result<()> {
+    let time = format_utc(sample.time);
+    let secs = sample.at.duration_since(previous.at).as_secs_f64().max(0.001);
+    let delta = |before: u64, after: u64| after.saturating_sub(before) as f64;
+    let per_sec = |before: u64, after: u64| delta(before, after) / secs;
+    let find = |counters: &[(String, Vec<u64>)], name: &str| counters.iter().find(|counter| counter.0 == name).map(|counter| counter.1.clone());
+
+    for (cpu, ticks) in sample.cpus.iter() {
+        let before = find(&previous.cpus, cpu).unwrap_or_default();
+        let deltas: Vec<f64> = (0..8).map(|i| delta(*before.get(i).unwrap_or(&0), *ticks.get(i).unwrap_or(&0))).collect();
+        let total: f64 = deltas.iter().sum::<f64>().max(1.0);
+        let name = if "cpu" == cpu { "all" } else { &cpu[3..] };
+        let percents: Vec<String> = deltas.iter().map(|ticks| format!("{:.1}", ticks * 100.0 / total)).collect();
+        writeln!(files.cpu, "{},{},{}", time, name, percents.join(","))?;
+    }
+
+    let stat = |name: &str| *sample.stat.get(name).unwrap_or(&0);
+    let stat_rate = |name: &str| per_sec(*previous.stat.get(name).unwrap_or(&0), stat(name));
+    let mem = |name: &str| *sample.meminfo.get(name).unwrap_or(&0);
+    let vm_rate = |name: &str| per_sec(*previous.vmstat.get(name).unwrap_or(&0), *sample.vmstat.get(name).unwrap_or(&0));
+    writeln!(
+        files.system,
+        "{},{},{},{:.0},{:.0},{},{},{},{},{},{},{},{},{:.0},{:.0},{:.0},{:.0},{:.0},{:.0}",
+        time, stat("procs_running"), stat("procs_blocked"), stat_rate("ctxt"), stat_rate("intr"),
+        mem("MemTotal"), mem("MemFree"), mem("MemAvailable"), mem("Buffers"), mem("Cached"), mem("Dirty"), mem("Writeback"),
+        mem("SwapTotal").saturating_sub(mem("SwapFree")),
+        vm_rate("pgpgin"), vm_rate("pgpgout"), vm_rate("pswpin"), vm_rate("pswpout"), vm_rate("pgfault"), vm_rate("pgmajfault")
+    )?;
+
+    // reads reads_merged sectors_read ms_reading writes writes_merged sectors_written ms_writing in_progress ms_doing_io weighted_ms
+    for (disk, counters) in sample.disks.iter() {
+        let before = match find(&previous.disks, disk) {
+            Some(before) => before,
+            None => continue,
+        };
+        let d = |i: usize| delta(before[i], counters[i]);
+        let millis = secs * 1000.0;
+        writeln!(
+            files.disks,
+            "{},{},{:.1},{:.1},{:.1},{:.1},{:.1},{:.1},{:.2},{:.2},{:.2},{:.1}",
+            time, disk, d(0) / secs, d(4) / secs, d(2) / 2.0 / secs, d(6) / 2.0 / secs, d(1) / secs, d(5) / secs,
+            if 0.0 < d(0) { d(3) / d(0) } else { 0.0 }, if 0.0 < d(4) { d(7) / d(4) } else { 0.0 },
+            d(10) / millis, (d(9) * 100.0 / millis).min(100.0)
+        )?;
+    }
+
+    // rx: bytes packets errs drop fifo frame compressed multicast, tx: bytes packets errs drop …
+    for (nic, counters) in sample.nics.iter() {
+        let before = match find(&previous.nics, nic) {
+            Some(before) => before,
+            None => continue,
+        };
+        let rate = |i: usize| per_sec(before[i], counters[i]);
+        writeln!(
+            files.network,
+            "{},{},{:.1},{:.1},{:.1},{:.1},{:.1},{:.1},{:.1},{:.1}",
+            time, nic, rate(0) / 1024.0, rate(8) / 1024.0, rate(1), rate(9), rate(2), rate(10), rate(3), rate(11)
+        )?;
+    }
+
+    if let (Some(before), Some(process)) = (&previous.process, &sample.process) {
+        let (read_kb_s, write_kb_s) = match (before.io, process.io) {
+            (Some(io_before), Some(io)) => (format!("{:.1}", per_sec(io_before.0, io.0) / 1024.0), format!("{:.1}", per_sec(io_before.1, io.1) / 1024.0)),
+            _ => (String::new(), String::new()),
+        };
+        writeln!(
+            files.process,
+            "{},{},{},{},{},{:.1},{:.1},{:.1},{},{}",
+            time, options.cassandra_pid, process.state, process.threads, process.rss_kb,
+            delta(before.cpu_ticks, process.cpu_ticks) / USER_HZ / secs * 100.0,
+            per_sec(before.minor_faults, process.minor_faults), per_sec(before.major_faults, process.major_faults),
+            read_kb_s, write_kb_s
+        )?;
+    }
+    Ok(())
+}
+
+const REDACTED: &str = "****REDACTED****";
+
+/** Comma separated, case-insensitive, patterns of key names whose values are secret,
+ * `*` matches any characters, and a leading `!` excludes matching keys (eg durations and paths).
+ **/
+const DEFAULT_REDACT_RULES: &str = "*password*,*passwd*,*secret*,*private_key*,*access_key*,*auth_token*,!*file*,!*path*,!*_interval*,!*_in_ms,!*_entries";
+
+struct RedactionRules {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    // (name, value) of known secrets, masked wherever they appear
+    literals: Vec<(String, String)>,
+}
+
+impl RedactionRules {
+    fn new(options: &Options) -> RedactionRules {
+        let mut rules = RedactionRules { include: Vec::new(), exclude: Vec::new(), literals: Vec::new() };
+        for rule in options.redact_rules.split(',').map(|rule| rule.trim().to_lowercase()).filter(|rule| !rule.is_empty()) {
+            if let Some(excluded) = rule.strip_prefix('!') {
+                rules.exclude.push(excluded.to_string());
+            } else {
+                rules.include.push(rule);
+            }
+        }
+        for (name, value) in [("jmxPassword", options.jmx_password), ("cqlshPassword", options.cqlsh_password)].iter() {
+            // very short values would mask too much unrelated text
+            if 4 <= value.len() {
+                rules.literals.push((name.to_string(), value.to_string()));
+            }
+        }
+        rules
+    }
+
+    fn is_secret_key(&self, key: &str) -> bool {
+        let key = key.to_lowercase();
+        self.include.iter().any(|rule| glob_match(rule.as_bytes(), key.as_bytes()))
+            && !self.exclude.iter().any(|rule| glob_match(rule.as_bytes(), key.as_bytes()))
+    }
+}
+
+// `*` matches any (including no) characters
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        Some((first, rest)) => text.first() == Some(first) && glob_match(rest, &text[1..]),
+    }
+}
+
+/** Nodetool output parsed into JSON next to its text, eg nodetool/status.json, so the tools reading bundles don't each parse the text
+ *
+ * Tolerant of the output differences across Cassandra 3.0 to 5.0 and DSE 5.1 and 6.x: columns are matched by their header's names,
+ * and what isn't recognised is left out. Keys are the output's names in snake_case, values are numbers when they are numbers.
+ **/
+const NODETOOL_PARSERS: &[(&str, NodetoolParser)] = &[
+    ("status", parse_nodetool_status),
+    ("tpstats", parse_nodetool_tpstats),
+    ("info", parse_nodetool_info),
+    ("ring", parse_nodetool_ring),
+    ("compactionstats", parse_nodetool_compactionstats),
+    // tablestats, under its older name
+    ("cfstats", parse_nodetool_cfstats),
+    ("proxyhistograms", parse_nodetool_proxyhistograms),
+    ("tablehistograms", parse_nodetool_tablehistograms),
+    ("netstats", parse_nodetool_netstats),
+    ("gossipinfo", parse_nodetool_gossipinfo),
+    ("describecluster", parse_nodetool_describecluster),
+];
+
+// the output's text into its JSON, or why it couldn't be
+type NodetoolParser = fn(&str) -> Result<String, String>;
+
+fn structure_nodetool_output(options: &Options, producers: &mut HashMap<String, String>) {
+    println!("Parsing nodetool output into JSON…");
+    for (name, parse) in NODETOOL_PARSERS.iter() {
+        let text = match std::fs::read_to_string(format!("{}/nodetool/{}.txt", options.artifact_dir, name)) {
+            Ok(text) if !text.trim().is_empty() => text,
+            _ => continue,
+        };
+        let json_file = format!("nodetool/{}.json", name);
+        // nodetool's errors (eg failing to connect) are in the output
+        let first_line = text.trim_start().lines().next().unwrap_or("");
+        let result = if first_line.starts_with("nodetool: ") || first_line.starts_with("error: ") {
+            Err(first_line.to_string())
+        } else {
+            parse(&text)
+        };
+        let result = result.and_then(|json| std::fs::write(format!("{}/{}", options.artifact_dir, json_file), json + "\n").map_err(|error| format!("failed to write {}: {}", json_file, error)));
+        match result {
+            Ok(()) => {
+                println!("\tnodetool/{}.txt > {}… OK", name, json_file);
+                producers.insert(bundle_path(&format!("{}/{}", options.artifact_dir, json_file)), format!("native: nodetool/{}.txt", name));
+            }
+            Err(error) => println!("\tnodetool/{}.txt… failed: {}", name, error),
+        }
+    }
+    println!(" …OK");
+}
+
+/** `Space used (live)` as space_used_live **/
+fn json_key(name: &str) -> String {
+    let mut key = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            key.push(c.to_ascii_lowercase());
+        } else if !key.is_empty() && !key.ends_with('_') {
+            key.push('_');
+        }
+    }
+    key.trim_end_matches('_').to_string()
+}
+
+/** The value as a JSON number when it is one (as written, so long integers keep their digits), or true or false, otherwise a string **/
+fn json_value(value: &str) -> String {
+    let value = value.trim();
+    if "true" == value || "false" == value || is_json_number(value) {
+        value.to_string()
+    } else {
+        json_string(value)
+    }
+}
+
+fn is_json_number(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let (mantissa, exponent) = match digits.find(['e', 'E']) {
+        Some(at) => (&digits[..at], Some(&digits[at + 1..])),
+        None => (digits, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+    let all_digits = |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+    all_digits(integer)
+        && !(1 < integer.len() && integer.starts_with('0'))
+        && fraction.is_none_or(all_digits)
+        && exponent.is_none_or(|exponent| all_digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)))
+}
+
+/** Columns are separated by at least two spaces, names can have one (eg `Host ID`) **/
+fn split_columns(line: &str) -> Vec<&str> {
+    line.split("  ").map(|column| column.trim()).filter(|column| !column.is_empty()).collect()
+}
+
+/** Columns separated by as little as one space, the known names of more than one word (eg `All time blocked`) kept whole **/
+fn header_columns(words: &[&str], known: &[&str]) -> Vec<String> {
+    let mut columns = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let known = known.iter().find(|name| name.split(' ').enumerate().all(|(j, word)| words.get(i + j) == Some(&word)));
+        let name = known.map(|name| name.to_string()).unwrap_or(words[i].to_string());
+        i += name.split(' ').count();
+        columns.push(name);
+    }
+    columns
+}
+
+/** Adds the key once, the first value wins **/
+fn add_json_value(object: &mut JsonObject, keys: &mut HashSet<String>, name: &str, value: &str) {
+    let key = json_key(name);
+    if !key.is_empty() && keys.insert(key.clone()) {
+        object.raw(&key, &json_value(value));
+    }
+}
+
+/** A load (`123.45 KiB`, `1.2 GB` or `?`) at the start of the values, as bytes, and the values after it **/
+fn take_load<'v>(values: &'v [&'v str]) -> (Option<u64>, &'v [&'v str]) {
+    if 2 <= values.len() && f64::from_str(values[0]).is_ok() && values[1].starts_with(|c: char| c.is_ascii_alphabetic()) {
+        (parse_log_size(&format!("{} {}", values[0], values[1])).map(|bytes| bytes.round() as u64), &values[2..])
+    } else if let Some((load, rest)) = values.split_first() {
+        (parse_log_size(load).map(|bytes| bytes.round() as u64), rest)
+    } else {
+        (None, values)
+    }
+}
+
+fn parse_percent(value: &str) -> Option<f64> {
+    value.strip_suffix('%').and_then(|percent| f64::from_str(percent).ok())
+}
+
+/** `UN  10.0.0.1  123.45 KiB  256  33.3%  <host id>  rack1` under each `Datacenter: dc1` **/
+fn parse_nodetool_status(text: &str) -> Result<String, String> {
+    let mut nodes: Vec<String> = Vec::new();
+    let (mut datacenter, mut columns) = ("", Vec::new());
+    for line in text.lines() {
+        if let Some(name) = line.strip_prefix("Datacenter:") {
+            datacenter = name.trim();
+            continue;
+        }
+        // the columns after `--` and Address
+        if line.starts_with("--") {
+            columns = split_columns(line).into_iter().skip(2).collect();
+            continue;
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let code = tokens.first().map(|code| code.as_bytes()).unwrap_or(&[]);
+        if columns.is_empty() || tokens.len() < 3 || 2 != code.len() || !b"UD".contains(&code[0]) || !b"NLJM".contains(&code[1]) {
+            continue;
+        }
+        let state = match code[1] {
+            b'N' => "Normal",
+            b'L' => "Leaving",
+            b'J' => "Joining",
+            _ => "Moving",
+        };
+        let mut node = JsonObject::new();
+        node.string("datacenter", datacenter).string("address", tokens[1]).string("status", if b'U' == code[0] { "Up" } else { "Down" }).string("state", state);
+        let mut keys: HashSet<String> = HashSet::new();
+        let mut values = &tokens[2..];
+        for column in columns.iter() {
+            if values.is_empty() {
+                break;
+            }
+            if "Load" == *column {
+                let (load, rest) = take_load(values);
+                node.number_or_null("load_bytes", load);
+                values = rest;
+                continue;
+            }
+            let value = values[0];
+            values = &values[1..];
+            match *column {
+                // the node's token, with a single token per node
+                "Token" => node.string("token", value),
+                "Tokens" | "VNodes" => node.raw("tokens", &json_value(value)),
+                "Host ID" => node.string("host_id", value),
+                "Rack" => node.string("rack", value),
+                column if column.starts_with("Owns") => node.number_or_null("owns_percent", parse_percent(value)),
+                column => {
+                    add_json_value(&mut node, &mut keys, column, value);
+                    &mut node
+                }
+            };
+        }
+        nodes.push(node.build());
+    }
+    if nodes.is_empty() {
+        return Err("no nodes found".to_string());
+    }
+    Ok(JsonObject::new().raw("nodes", &json_array(&nodes)).build())
+}
+
+/** Columns of the thread pools, separated by only one space since 4.0 **/
+const TPSTATS_COLUMNS: &[&str] = &["Pool Name", "All time blocked"];
+
+/** The thread pools (`Pool Name` and its columns) and the dropped messages (`Message type`, with their latencies since 4.0) **/
+fn parse_nodetool_tpstats(text: &str) -> Result<String, String> {
+    let (mut pools, mut dropped): (Vec<String>, Vec<String>) = (Vec::new(), Vec::new());
+    let (mut columns, mut in_pools, mut in_dropped) = (Vec::new(), false, false);
+    for line in text.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if line.starts_with("Pool Name") {
+            columns = header_columns(&tokens, TPSTATS_COLUMNS).into_iter().skip(1).collect();
+            in_pools = true;
+            in_dropped = false;
+        } else if line.starts_with("Message type") {
+            in_pools = false;
+            in_dropped = true;
+        } else if tokens.is_empty() {
+            in_pools = false;
+        } else if in_pools && 2 <= tokens.len() {
+            let mut pool = JsonObject::new();
+            pool.string("name", tokens[0]);
+            let mut keys: HashSet<String> = HashSet::new();
+            // DSE 6 adds eg `(N/A)` after some values
+            let values = tokens[1..].iter().filter(|value| !value.starts_with('('));
+            for (column, value) in columns.iter().zip(values) {
+                add_json_value(&mut pool, &mut keys, column, value);
+            }
+            pools.push(pool.build());
+        } else if in_dropped && 2 <= tokens.len() && !tokens[0].starts_with(|c: char| c.is_ascii_digit()) {
+            let mut message = JsonObject::new();
+            message.string("type", tokens[0]).raw("dropped", &json_value(tokens[1]));
+            if 6 <= tokens.len() {
+                let latency = JsonObject::new().raw("p50", &json_value(tokens[2])).raw("p95", &json_value(tokens[3])).raw("p99", &json_value(tokens[4])).raw("max", &json_value(tokens[5])).build();
+                message.raw("latency_waiting_in_queue_micros", &latency);
+            }
+            dropped.push(message.build());
+        }
+    }
+    if pools.is_empty() {
+        return Err("no thread pools found".to_string());
+    }
+    Ok(JsonObject::new().raw("thread_pools", &json_array(&pools)).raw("dropped_messages", &json_array(&dropped)).build())
+}
+
+/** `Name : value` lines, with the load in bytes and the heap's used and total MB **/
+fn parse_nodetool_info(text: &str) -> Result<String, String> {
+    let mut info = JsonObject::new();
+    let mut keys: HashSet<String> = HashSet::new();
+    for (name, value) in text.lines().filter_map(|line| line.split_once(':')) {
+        let (name, value) = (name.trim(), value.trim());
+        match name {
+            "Load" => {
+                keys.insert("load_bytes".to_string());
+                info.number_or_null("load_bytes", parse_log_size(value).map(|bytes| bytes.round() as u64));
+            }
+            // used / total
+            "Heap Memory (MB)" => {
+                if let Some((used, total)) = value.split_once('/') {
+                    keys.insert("heap_memory_used_mb".to_string());
+                    info.raw("heap_memory_used_mb", &json_value(used)).raw("heap_memory_total_mb", &json_value(total));
+                }
+            }
+            _ => {}
+        }
+        add_json_value(&mut info, &mut keys, name, value);
+    }
+    if !keys.contains("id") && !keys.contains("load_bytes") {
+        return Err("no ID or Load found".to_string());
+    }
+    Ok(info.build())
+}
+
+/** `10.0.0.1  rack1  Up  Normal  123.45 KiB  33.3%  -9223372036854775808` under each `Datacenter: dc1` **/
+fn parse_nodetool_ring(text: &str) -> Result<String, String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut datacenter = "";
+    for line in text.lines() {
+        if let Some(name) = line.strip_prefix("Datacenter:") {
+            datacenter = name.trim();
+            continue;
+        }
+        let values: Vec<&str> = line.split_whitespace().collect();
+        if values.len() < 6 || !["Up", "Down", "?"].contains(&values[2]) {
+            continue;
+        }
+        let (load, rest) = take_load(&values[4..]);
+        if rest.len() < 2 {
+            continue;
+        }
+        let token = JsonObject::new()
+            .string("datacenter", datacenter)
+            .string("address", values[0])
+            .string("rack", values[1])
+            .string("status", values[2])
+            .string("state", values[3])
+            .number_or_null("load_bytes", load)
+            .number_or_null("owns_percent", parse_percent(rest[0]))
+            .string("token", rest[rest.len() - 1])
+            .build();
+        tokens.push(token);
+    }
+    if tokens.is_empty() {
+        return Err("no tokens found".to_string());
+    }
+    Ok(JsonObject::new().raw("tokens", &json_array(&tokens)).build())
+}
+
+/** `pending tasks: 5`, per table since 3.11 (`- ks.table: 3`), and the active compactions, whose types can have spaces **/
+fn parse_nodetool_compactionstats(text: &str) -> Result<String, String> {
+    let (mut pending, mut remaining): (Option<&str>, Option<&str>) = (None, None);
+    let (mut pending_tables, mut compactions): (Vec<String>, Vec<String>) = (Vec::new(), Vec::new());
+    let mut with_id = true;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let tokens: Vec<&str> = trimmed.split_whitespace().collect();
+        if let Some(tasks) = trimmed.strip_prefix("pending tasks:") {
+            pending = Some(tasks.trim());
+        } else if let Some((_, time)) = trimmed.split_once("remaining time").and_then(|(_, time)| time.split_once(':')) {
+            remaining = Some(time.trim());
+        } else if let Some((table, tasks)) = trimmed.strip_prefix("- ").and_then(|table| table.rsplit_once(':')) {
+            let (keyspace, table) = table.split_once('.').unwrap_or(("", table));
+            pending_tables.push(JsonObject::new().string("keyspace", keyspace).string("table", table).raw("pending", &json_value(tasks)).build());
+        } else if trimmed.contains("compaction type") {
+            with_id = trimmed.starts_with("id");
+        } else if 7 <= tokens.len() && tokens[tokens.len() - 1].ends_with('%') {
+            let n = tokens.len();
+            let (id, kind) = if with_id { (Some(tokens[0]), tokens[1..n - 6].join(" ")) } else { (None, tokens[..n - 6].join(" ")) };
+            let compaction = JsonObject::new()
+                .string_or_null("id", id)
+                .string("type", &kind)
+                .string("keyspace", tokens[n - 6])
+                .string("table", tokens[n - 5])
+                .raw("completed", &json_value(tokens[n - 4]))
+                .raw("total", &json_value(tokens[n - 3]))
+                .string("unit", tokens[n - 2])
+                .number_or_null("progress_percent", parse_percent(tokens[n - 1]))
+                .build();
+            compactions.push(compaction);
+        }
+    }
+    let pending = pending.ok_or("no pending tasks found")?;
+    Ok(JsonObject::new()
+        .raw("pending_tasks", &json_value(pending))
+        .raw("pending_tasks_by_table", &json_array(&pending_tables))
+        .raw("compactions", &json_array(&compactions))
+        .string_or_null("remaining_time", remaining)
+        .build())
+}
+
+// a JSON object with the keys added to it
+type KeyedObject = (JsonObject, HashSet<String>);
+
+/** Each `Keyspace : ks` with its statistics and its `Table: t` (`Column Family: t` before 3.0) with theirs **/
+fn parse_nodetool_cfstats(text: &str) -> Result<String, String> {
+    // (keyspace, its statistics, its tables' (name, statistics))
+    let mut keyspaces: Vec<(JsonObject, HashSet<String>, Vec<KeyedObject>)> = Vec::new();
+    let mut total_tables: Option<&str> = None;
+    for (name, value) in text.lines().filter_map(|line| line.split_once(':')) {
+        let (name, value) = (name.trim(), value.trim());
+        if "Total number of tables" == name {
+            total_tables = Some(value);
+        } else if "Keyspace" == name {
+            let mut keyspace = JsonObject::new();
+            keyspace.string("keyspace", value);
+            keyspaces.push((keyspace, HashSet::new(), Vec::new()));
+        } else if let Some((_, _, tables)) = keyspaces.last_mut().filter(|_| name.starts_with("Table") || "Column Family" == name) {
+            let mut table = JsonObject::new();
+            table.string("table", value).boolean("index", name.contains("index"));
+            tables.push((table, HashSet::new()));
+        } else if let Some((keyspace, keys, tables)) = keyspaces.last_mut() {
+            match tables.last_mut() {
+                Some((table, keys)) => add_json_value(table, keys, name, value),
+                None => add_json_value(keyspace, keys, name, value),
+            }
+        }
+    }
+    if keyspaces.is_empty() {
+        return Err("no keyspaces found".to_string());
+    }
+    let keyspaces: Vec<String> = keyspaces
+        .iter_mut()
+        .map(|(keyspace, _, tables)| keyspace.raw("tables", &json_array(&tables.iter().map(|(table, _)| table.build()).collect::<Vec<String>>())).build())
+        .collect();
+    let mut cfstats = JsonObject::new();
+    if let Some(total_tables) = total_tables {
+        cfstats.raw("total_tables", &json_value(total_tables));
+    }
+    Ok(cfstats.raw("keyspaces", &json_array(&keyspaces)).build())
+}
+
+/** Columns of the histograms, matched by name as some are only separated by one space **/
+const HISTOGRAM_COLUMNS: &[&str] = &["CAS Read Latency", "CAS Write Latency", "View Write Latency", "Read Latency", "Write Latency", "Range Latency", "Partition Size", "Cell Count", "SSTables"];
+
+// a column's name and its (percentile, value) rows
+type HistogramColumn = (String, Vec<(String, String)>);
+
+/** Each histogram's title (`proxy histograms`, `ks/table histograms`) with its columns **/
+fn parse_histograms(text: &str) -> Vec<(String, Vec<HistogramColumn>)> {
+    let mut histograms: Vec<(String, Vec<HistogramColumn>)> = Vec::new();
+    for line in text.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if let Some(title) = line.trim().strip_suffix(" histograms") {
+            histograms.push((title.to_string(), Vec::new()));
+        } else if words.first() == Some(&"Percentile") {
+            if histograms.is_empty() {
+                histograms.push((String::new(), Vec::new()));
+            }
+            let columns = &mut histograms.last_mut().unwrap().1;
+            *columns = header_columns(&words[1..], HISTOGRAM_COLUMNS).into_iter().map(|name| (name, Vec::new())).collect();
+        } else if let Some((label, values)) = words.split_first() {
+            // 50%, 75%, 95%, 98%, 99%, Min and Max
+            let percentile = match *label {
+                "Min" => "min".to_string(),
+                "Max" => "max".to_string(),
+                label if label.ends_with('%') => format!("p{}", label.trim_end_matches('%')),
+                _ => continue,
+            };
+            if let Some((_, columns)) = histograms.last_mut() {
+                for ((_, rows), value) in columns.iter_mut().zip(values.iter()) {
+                    rows.push((percentile.clone(), value.to_string()));
+                }
+            }
+        }
+    }
+    histograms.retain(|(_, columns)| columns.iter().any(|(_, rows)| !rows.is_empty()));
+    histograms
+}
+
+fn histogram_json(object: &mut JsonObject, columns: &[HistogramColumn]) {
+    for (name, rows) in columns.iter() {
+        let unit = if name.ends_with("Latency") { "_micros" } else if name.ends_with("Size") { "_bytes" } else { "" };
+        let mut percentiles = JsonObject::new();
+        rows.iter().for_each(|(percentile, value)| {
+            percentiles.raw(percentile, &json_value(value));
+        });
+        object.raw(&format!("{}{}", json_key(name), unit), &percentiles.build());
+    }
+}
+
+fn parse_nodetool_proxyhistograms(text: &str) -> Result<String, String> {
+    let histograms = parse_histograms(text);
+    let (_, columns) = histograms.first().ok_or("no histogram found")?;
+    let mut proxy = JsonObject::new();
+    histogram_json(&mut proxy, columns);
+    Ok(proxy.build())
+}
+
+/** Since 4.0 without arguments it gives each table's histograms, under `ks/table histograms` **/
+fn parse_nodetool_tablehistograms(text: &str) -> Result<String, String> {
+    let histograms = parse_histograms(text);
+    if histograms.is_empty() {
+        return Err("no histogram found".to_string());
+    }
+    let tables: Vec<String> = histograms
+        .iter()
+        .map(|(title, columns)| {
+            let (keyspace, table) = title.split_once(['/', '.']).unwrap_or(("", title));
+            let mut histogram = JsonObject::new();
+            histogram.string("keyspace", keyspace).string("table", table);
+            histogram_json(&mut histogram, columns);
+            histogram.build()
+        })
+        .collect();
+    Ok(JsonObject::new().raw("tables", &json_array(&tables)).build())
+}
+
+/** The mode, the streams (as their lines), the read repair statistics and the messaging pools, whose names have spaces **/
+fn parse_nodetool_netstats(text: &str) -> Result<String, String> {
+    let mut mode: Option<&str> = None;
+    let (mut streams, mut pools): (Vec<&str>, Vec<String>) = (Vec::new(), Vec::new());
+    let mut read_repair = JsonObject::new();
+    let mut read_repair_keys: HashSet<String> = HashSet::new();
+    let (mut section, mut columns) = ("", Vec::new());
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(value) = trimmed.strip_prefix("Mode:") {
+            mode = Some(value.trim());
+            section = "streams";
+        } else if trimmed.starts_with("Read Repair Statistics") {
+            section = "read repair";
+        } else if trimmed.starts_with("Pool Name") {
+            columns = split_columns(trimmed).into_iter().skip(1).collect();
+            section = "pools";
+        } else if trimmed.is_empty() || "Not sending any streams." == trimmed {
+            continue;
+        } else if "streams" == section {
+            streams.push(trimmed);
+        } else if let Some((name, value)) = trimmed.split_once(':').filter(|_| "read repair" == section) {
+            add_json_value(&mut read_repair, &mut read_repair_keys, name, value);
+        } else if "pools" == section {
+            let tokens: Vec<&str> = trimmed.split_whitespace().collect();
+            let name_end = tokens.iter().position(|token| "n/a" == *token || token.starts_with(|c: char| c.is_ascii_digit())).unwrap_or(tokens.len());
+            let mut pool = JsonObject::new();
+            pool.string("name", &tokens[..name_end].join(" "));
+            let mut keys: HashSet<String> = HashSet::new();
+            for (column, value) in columns.iter().zip(tokens[name_end..].iter()) {
+                if "n/a" == *value {
+                    keys.insert(json_key(column));
+                    pool.raw(&json_key(column), "null");
+                } else {
+                    add_json_value(&mut pool, &mut keys, column, value);
+                }
+            }
+            pools.push(pool.build());
+        }
+    }
+    let mode = mode.ok_or("no Mode found")?;
+    Ok(JsonObject::new().string("mode", mode).raw("streams", &json_string_array(&streams)).raw("read_repair", &read_repair.build()).raw("pools", &json_array(&pools)).build())
+}
+
+/** Each endpoint (`/10.0.0.1`, or `host/10.0.0.1:7000` since 4.0) with its generation, heartbeat and states (`STATUS:14:NORMAL,…`, without the version before 3.0) **/
+fn parse_nodetool_gossipinfo(text: &str) -> Result<String, String> {
+    let mut endpoints: Vec<(JsonObject, JsonObject)> = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(|c: char| c.is_whitespace()) {
+            let (hostname, address) = line.trim().split_once('/').unwrap_or(("", line.trim()));
+            let mut endpoint = JsonObject::new();
+            endpoint.string("endpoint", line.trim()).string_or_null("hostname", Some(hostname).filter(|hostname| !hostname.is_empty())).string("address", address);
+            endpoints.push((endpoint, JsonObject::new()));
+            continue;
+        }
+        let (name, value) = match line.trim().split_once(':') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        if let Some((endpoint, states)) = endpoints.last_mut() {
+            if "generation" == name || "heartbeat" == name {
+                endpoint.raw(name, &json_value(value));
+                continue;
+            }
+            let (version, value) = match value.split_once(':') {
+                Some((version, value)) if u64::from_str(version).is_ok() => (Some(version), value),
+                _ => (None, value),
+            };
+            states.raw(name, &JsonObject::new().number_or_null("version", version).string("value", value).build());
+        }
+    }
+    if endpoints.is_empty() {
+        return Err("no endpoints found".to_string());
+    }
+    let endpoints: Vec<String> = endpoints.iter_mut().map(|(endpoint, states)| endpoint.raw("states", &states.build()).build()).collect();
+    Ok(JsonObject::new().raw("endpoints", &json_array(&endpoints)).build())
+}
+
+/** The cluster information, its schema versions, node counts, datacenters, database versions (4.0+) and keyspaces' replication (4.0+) **/
+fn parse_nodetool_describecluster(text: &str) -> Result<String, String> {
+    let mut cluster = JsonObject::new();
+    let mut keys: HashSet<String> = HashSet::new();
+    let mut stats = JsonObject::new();
+    let mut stats_keys: HashSet<String> = HashSet::new();
+    let (mut schema_versions, mut datacenters, mut database_versions, mut keyspaces) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let mut section = String::new();
+    // `version: [endpoints]`
+    let versions = |line: &str| {
+        line.split_once(": [").map(|(version, endpoints)| {
+            let endpoints: Vec<&str> = endpoints.trim_end_matches(']').split(',').map(|endpoint| endpoint.trim()).filter(|endpoint| !endpoint.is_empty()).collect();
+            JsonObject::new().string("version", version.trim()).raw("endpoints", &json_string_array(&endpoints)).build()
+        })
+    };
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if !line.starts_with(|c: char| c.is_whitespace()) && trimmed.ends_with(':') {
+            section = trimmed.trim_end_matches(':').to_string();
+            continue;
+        }
+        match section.as_str() {
+            "Stats for all nodes" => {
+                if let Some((name, value)) = trimmed.split_once(':') {
+                    add_json_value(&mut stats, &mut stats_keys, name, value);
+                }
+            }
+            // dc1 #Nodes: 3 #Down: 0
+            "Data Centers" => {
+                let (name, counts) = trimmed.split_once(" #Nodes:").unwrap_or((trimmed, ""));
+                let (nodes, down) = counts.split_once("#Down:").unwrap_or((counts, ""));
+                datacenters.push(JsonObject::new().string("name", name.trim()).raw("nodes", &json_value(nodes)).raw("down", &json_value(down)).build());
+            }
+            "Database versions" => database_versions.extend(versions(trimmed)),
+            // system_auth -> Replication class: NetworkTopologyStrategy {dc1=3}
+            "Keyspaces" => {
+                if let Some((name, replication)) = trimmed.split_once(" -> ") {
+                    let replication = replication.strip_prefix("Replication class:").unwrap_or(replication).trim();
+                    keyspaces.push(JsonObject::new().string("name", name.trim()).string("replication", replication).build());
+                }
+            }
+            _ => {
+                if "Schema versions:" == trimmed {
+                    section = "Schema versions".to_string();
+                } else if "Schema versions" == section {
+                    schema_versions.extend(versions(trimmed));
+                } else if let Some((name, value)) = trimmed.split_once(':') {
+                    add_json_value(&mut cluster, &mut keys, name, value);
+                }
+            }
+        }
+    }
+    if !keys.contains("name") {
+        return Err("no cluster Name found".to_string());
+    }
+    Ok(cluster
+        .raw("schema_versions", &json_array(&schema_versions))
+        .raw("stats", &stats.build())
+        .raw("datacenters", &json_array(&datacenters))
+        .raw("database_versions", &json_array(&database_versions))
+        .raw("keyspaces", &json_array(&keyspaces))
+        .build())
+}
+
+// logs larger than this are not redacted (and are listed in the report), rewriting multi-GB logs would take longer than the collection
+const REDACT_MAX_LOG_BYTES: u64 = 64 * MIB;
+
+/** Masks the secret values in the text files under the artifact dir, in place,
+ * and writes redaction-report.txt listing the files and keys masked (never the values).
+ * Configuration, environment and command output files are always redacted, logs only up to REDACT_MAX_LOG_BYTES.
+ **/
+fn redact_artifacts(options: &Options) {
+    println!("Redacting secrets from collected files…");
+    let rules = RedactionRules::new(options);
+    let mut files: Vec<PathBuf> = Vec::new();
+    list_files(Path::new(options.artifact_dir), &mut files);
+    files.sort();
+
+    let mut redacted = String::new();
+    let mut failed = String::new();
+    let mut too_large = String::new();
+    let (mut file_count, mut value_count) = (0, 0);
+    for path in files {
+        let relative = path.strip_prefix(options.artifact_dir).unwrap_or(&path).display().to_string();
+        let size = std::fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+        if is_log_file(&relative) && REDACT_MAX_LOG_BYTES < size {
+            too_large.push_str(&format!("{} ({} MB)\n", relative, size / MIB));
+            continue;
+        }
+        match redact_file(&path, &rules) {
+            Ok(ref keys) if keys.is_empty() => {}
+            Ok(keys) => {
+                file_count += 1;
+                redacted.push_str(&format!("{}\n", relative));
+                for (key, count) in keys {
+                    value_count += count;
+                    redacted.push_str(&format!("\t{} ({})\n", key, count));
+                }
+            }
+            Err(error) => {
+                println!("WARNING: failed to redact {}: {}", relative, error);
+                failed.push_str(&format!("{}: {}\n", relative, error));
+            }
+        }
+    }
+
+    let mut report = String::new();
+    report.push_str("collect-info redaction report\n");
+    report.push_str(&format!("rules: {}\n", options.redact_rules));
+    report.push_str(&format!("known secrets: {}\n", rules.literals.iter().map(|literal| literal.0.as_str()).collect::<Vec<&str>>().join(", ")));
+    report.push_str(&format!("values are replaced with {}, and are not recorded here\n\n", REDACTED));
+    report.push_str(&format!("redacted: {} values in {} files\n", value_count, file_count));
+    report.push_str(&redacted);
+    if !too_large.is_empty() {
+        report.push_str(&format!("\nnot redacted, logs larger than {} MB, check these files manually before sharing:\n{}", REDACT_MAX_LOG_BYTES / MIB, too_large));
+    }
+    if !failed.is_empty() {
+        report.push_str(&format!("\nFAILED to redact, check these files manually before sharing:\n{}", failed));
+    }
+    println!("\tredacted {} values in {} files", value_count, file_count);
+    if !too_large.is_empty() {
+        println!("WARNING: logs larger than {} MB were not redacted, see redaction-report.txt", REDACT_MAX_LOG_BYTES / MIB);
+    }
+    let report_path = format!("{}/redaction-report.txt", options.artifact_dir);
+    if let Err(error) = std::fs::write(&report_path, &report) {
+        eprintln!("WARNING: failed to write {}: {}", report_path, error);
+    }
+}
+
+// under a logs/ (or logs-window/) directory, of the host or an instance
+fn is_log_file(relative: &str) -> bool {
+    let mut dirs: Vec<&str> = relative.split('/').collect();
+    dirs.pop();
+    dirs.iter().any(|dir| "logs" == *dir || "logs-window" == *dir)
+}
+
+// regular files only, symlinks are not followed
+fn list_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => list_files(&entry.path(), files),
+                Ok(file_type) if file_type.is_file() => files.push(entry.path()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/** Redacts the file when it is text (no NUL bytes in its first 8KB), returning the keys masked and their counts.
+ * Files are read once to find secrets, and only rewritten (via a temporary file and rename, keeping their permissions) when some are found.
+ **/
+fn redact_file(path: &Path, rules: &RedactionRules) -> io::Result<Vec<(String, usize)>> {
+    let mut head = [0u8; 8192];
+    let read = File::open(path)?.read(&mut head)?;
+    if head[..read].contains(&0) {
+        return Ok(Vec::new());
+    }
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    redact_lines(path, rules, &mut counts, None)?;
+    if !counts.is_empty() {
+        let temporary = PathBuf::from(format!("{}.redacting", path.display()));
+        let result = File::create(&temporary)
+            .and_then(|mut output| redact_lines(path, rules, &mut HashMap::new(), Some(&mut output)))
+            // eg a 0600 cassandra.yaml stays 0600, rather than taking the umask's mode
+            .and_then(|_| std::fs::set_permissions(&temporary, std::fs::metadata(path)?.permissions()))
+            .and_then(|_| std::fs::rename(&temporary, path));
+        if result.is_err() {
+            std::fs::remove_file(&temporary).ok();
+        }
+        result?;
+    }
+    let mut keys: Vec<(String, usize)> = counts.into_iter().collect();
+    keys.sort();
+    Ok(keys)
+}
+
+fn redact_lines(path: &Path, rules: &RedactionRules, counts: &mut HashMap<String, usize>, mut output: Option<&mut File>) -> io::Result<()> {
+    let mut reader = io::BufReader::new(File::open(path)?);
+    let mut line: Vec<u8> = Vec::new();
+    let mut writer = output.as_mut().map(io::BufWriter::new);
+    while 0 < reader.read_until(b'\n', &mut line)? {
+        let redacted = redact_line(&line, rules, counts);
+        if let Some(ref mut writer) = writer {
+            writer.write_all(&redacted)?;
+        }
+        line.clear();
+    }
+    if let Some(ref mut writer) = writer {
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn is_key_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b'_' == byte || b'.' == byte || b'-' == byte
+}
+
+/** Masks the values of secret keys in one line, in the forms
+ *  `key: value` (yaml, to the end of the line), `key=value` and `key = value` (properties, env, `-Dkey=value`),
+ *  and `"key": "value"` (json), plus any occurrence of the known secrets.
+ **/
+fn redact_line(line: &[u8], rules: &RedactionRules, counts: &mut HashMap<String, usize>) -> Vec<u8> {
+    let mut redacted: Vec<u8> = Vec::with_capacity(line.len());
+    let mut i = 0;
+    while i < line.len() {
+        if !is_key_byte(line[i]) || (0 < i && is_key_byte(line[i - 1])) {
+            redacted.push(line[i]);
+            i += 1;
+            continue;
+        }
+        let mut end = i;
+        while end < line.len() && is_key_byte(line[end]) {
+            end += 1;
+        }
+        let key = String::from_utf8_lossy(&line[i..end]).trim_start_matches('-').to_string();
+        redacted.extend_from_slice(&line[i..end]);
+        i = end;
+        if rules.is_secret_key(&key) {
+            if let Some((value_start, value_end)) = find_secret_value(line, end) {
+                redacted.extend_from_slice(&line[end..value_start]);
+                redacted.extend_from_slice(REDACTED.as_bytes());
+                i = value_end;
+                *counts.entry(key).or_insert(0) += 1;
+            }
+        }
+    }
+    for (name, value) in rules.literals.iter() {
+        let mut replaced: Vec<u8> = Vec::with_capacity(redacted.len());
+        let mut rest: &[u8] = &redacted;
+        while let Some(position) = rest.windows(value.len()).position(|window| window == value.as_bytes()) {
+            replaced.extend_from_slice(&rest[..position]);
+            replaced.extend_from_slice(REDACTED.as_bytes());
+            rest = &rest[position + value.len()..];
+            *counts.entry(name.to_string()).or_insert(0) += 1;
+        }
+        replaced.extend_from_slice(rest);
+        redacted = replaced;
+    }
+    redacted
+}
+
+/** The byte range of the value following a key ending at `position`, if there is a non-empty one **/
+fn find_secret_value(line: &[u8], position: usize) -> Option<(usize, usize)> {
+    let skip_blanks = |mut j: usize| {
+        while j < line.len() && (b' ' == line[j] || b'\t' == line[j]) {
+            j += 1;
+        }
+        j
+    };
+    let mut j = position;
+    // the closing quote of a json key
+    if j < line.len() && (b'"' == line[j] || b'\'' == line[j]) {
+        j += 1;
+    }
+    j = skip_blanks(j);
+    let separator = *line.get(j)?;
+    if (b'=' != separator && b':' != separator) || Some(&separator) == line.get(j + 1) {
+        return None;
+    }
+    j = skip_blanks(j + 1);
+    let line_end = line.iter().rposition(|byte| b'\n' != *byte && b'\r' != *byte).map(|last| last + 1).unwrap_or(0);
+    if line_end <= j {
+        return None;
+    }
+    let (start, end) = if b'"' == line[j] || b'\'' == line[j] {
+        let close = line[j + 1..line_end].iter().position(|byte| *byte == line[j])?;
+        (j + 1, j + 1 + close)
+    } else if b':' == separator {
+        // yaml values run to the end of the line, or a comment
+        let comment = line[j..line_end].windows(2).position(|window| window == b" #").map(|at| j + at).unwrap_or(line_end);
+        let end = line[j..comment].iter().rposition(|byte| b' ' != *byte && b'\t' != *byte).map(|last| j + last + 1).unwrap_or(j);
+        (j, end)
+    } else {
+        let end = line[j..line_end].iter().position(|byte| byte.is_ascii_whitespace()).map(|at| j + at).unwrap_or(line_end);
+        (j, end)
+    };
+    if start == end || REDACTED.as_bytes() == &line[start..end] {
+        None
+    } else {
+        Some((start, end))
+    }
+}
+
+/** MANIFEST, at the root of the artifact dir, is the bundle's provenance
+ * a header of which collector build collected what, where and when, then every file with its SHA-256, size, collection time and producing command.
+ *
+ * It is written once collecting is done, and refreshed by `collect-info archive` (or `collect-info manifest`) to include files added afterwards (eg DSE Search cores).
+ * Paths are escaped (\\ \t \n \r) so that each file is one line of tab separated columns.
+ **/
+const BUNDLE_MANIFEST: &str = "MANIFEST";
+
+// files collect-info writes itself, listed as produced by collect-info
+const COLLECT_INFO_FILES: &[&str] = &[
+    "collect-info.audit.log",
+    "collect-info.audit.jsonl",
+    "collect-info.summary.txt",
+    "collect-info.processes.txt",
+    "redaction-report.txt",
+    "execute_schema.cql",
+    "execute_metadata.cql",
+];
+
+struct BundleFile {
+    sha256: String,
+    size: u64,
+    path: String,
+    command: String,
+}
+
+// the header's (key, value) lines, and the files listed
+type BundleManifest = (Vec<(String, String)>, Vec<BundleFile>);
+
+fn escape_manifest_path(path: &str) -> String {
+    path.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+fn unescape_manifest_path(escaped: &str) -> String {
+    let mut path = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        match (c, if '\\' == c { chars.next() } else { None }) {
+            ('\\', Some('t')) => path.push('\t'),
+            ('\\', Some('n')) => path.push('\n'),
+            ('\\', Some('r')) => path.push('\r'),
+            ('\\', Some(escaped)) => path.push(escaped),
+            (c, _) => path.push(c),
+        }
+    }
+    path
+}
+
+/** The header's collector, host and Cassandra process lines, the caller adds the instances and times **/
+fn bundle_header(options: &Options) -> Vec<(String, String)> {
+    let mut header: Vec<(String, String)> = vec![
+        ("collector_version".to_string(), env::var("collectInfoVersion").ok().filter(|version| !version.trim().is_empty()).unwrap_or("unknown".to_string())),
+        ("host".to_string(), read_trimmed("/proc/sys/kernel/hostname")),
+        ("cassandra_pid".to_string(), if options.cassandra_pid.is_empty() { "none".to_string() } else { options.cassandra_pid.to_string() }),
+        ("cassandra_version".to_string(), release_version(options)),
+    ];
+    if let Ok(dse_version) = env::var("dse_version") {
+        if !dse_version.trim().is_empty() {
+            header.push(("dse_version".to_string(), dse_version.trim().to_string()));
+        }
+    }
+    header
+}
+
+/** From the collected `nodetool version` **/
+fn release_version(options: &Options) -> String {
+    std::fs::read_to_string(format!("{}/nodetool/version.txt", options.artifact_dir))
+        .unwrap_or_default()
+        .lines()
+        .find_map(|line| line.strip_prefix("ReleaseVersion:").map(|version| version.trim().to_string()))
+        .unwrap_or("unknown".to_string())
+}
+
+/** Which command wrote each file, keyed by path, a key ending with '/' covers everything below it **/
+fn record_command_producers(commands: &[Cmd], outcomes: &[(usize, Outcome)], options: &Options, producers: &mut HashMap<String, String>) {
+    for (i, outcome) in outcomes.iter() {
+        if let Outcome::Skipped(_) = *outcome {
+            continue;
+        }
+        let cmd = &commands[*i];
+        let command = format!("{} {}", format_command(cmd.command, options), format_args(cmd.args, options, true)).trim_end().to_string();
+        let (stdout, stderr) = output_files(*i, cmd);
+        let mut outputs: Vec<String> = vec![format!("{}/{}", options.artifact_dir, stdout), format!("{}/{}", options.artifact_dir, stderr)];
+        // commands without an output file write into the artifact dir given as an argument, eg cp -r … {artifact_dir}/os/
+        outputs.extend(format_arg_list(cmd.args, options, true).into_iter().filter(|arg| arg.starts_with(&format!("{}/", options.artifact_dir))));
+        for output in outputs {
+            let directory = output.ends_with('/') || Path::new(&output).is_dir();
+            producers.insert(format!("{}{}", bundle_path(&output), if directory { "/" } else { "" }), command.clone());
+        }
+    }
+}
+
+fn record_collect_info_producers(options: &Options, producers: &mut HashMap<String, String>) {
+    for file in COLLECT_INFO_FILES.iter() {
+        producers.insert(bundle_path(&format!("{}/{}", options.artifact_dir, file)), "collect-info".to_string());
+    }
+}
+
+/** Paths as keys, without doubled or trailing slashes **/
+fn bundle_path(path: &str) -> String {
+    path.split('/').filter(|part| !part.is_empty()).fold(String::new(), |path, part| path + "/" + part)
+}
+
+fn file_producer<'p>(producers: &'p HashMap<String, String>, path: &str) -> &'p str {
+    producers
+        .iter()
+        .filter(|(key, _)| path == key.as_str() || (key.ends_with('/') && path.starts_with(key.as_str())))
+        .max_by_key(|(key, _)| key.len())
+        .map(|(_, producer)| producer.as_str())
+        .unwrap_or("-")
+}
+
+/** Writes MANIFEST listing every file in the artifact dir (except itself), returning how many were listed **/
+fn write_bundle_manifest(artifact_dir: &str, header: &[(String, String)], producers: &HashMap<String, String>) -> Result<usize, String> {
+    let root = Path::new(artifact_dir);
+    let mut paths: Vec<PathBuf> = Vec::new();
+    list_files(root, &mut paths);
+    let mut relative: Vec<String> = paths
+        .iter()
+        .filter_map(|path| path.strip_prefix(root).ok().map(|path| path.to_string_lossy().to_string()))
+        .filter(|path| BUNDLE_MANIFEST != path)
+        .collect();
+    relative.sort();
+    let manifest_path = format!("{}/{}", artifact_dir, BUNDLE_MANIFEST);
+    let failed = |error: io::Error| format!("failed to write {}: {}", manifest_path, error);
+    let mut manifest = io::BufWriter::new(File::create(&manifest_path).map_err(failed)?);
+    writeln!(manifest, "# ds-collector artifact MANIFEST").map_err(failed)?;
+    for (key, value) in header.iter() {
+        writeln!(manifest, "{}: {}", key, value).map_err(failed)?;
+    }
+    writeln!(manifest, "\n# sha256\tsize\tcollected\tpath\tcommand").map_err(failed)?;
+    for path in relative.iter() {
+        let full_path = format!("{}/{}", artifact_dir, path);
+        let (sha256, size) = sha256_file(&full_path).map_err(|error| format!("failed to read {}: {}", full_path, error))?;
+        let collected = std::fs::metadata(&full_path).and_then(|metadata| metadata.modified()).map(format_utc).unwrap_or("-".to_string());
+        let command = file_producer(producers, &bundle_path(&full_path)).replace(['\t', '\n', '\r'], " ");
+        writeln!(manifest, "{}\t{}\t{}\t{}\t{}", sha256, size, collected, escape_manifest_path(path), command).map_err(failed)?;
+    }
+    manifest.flush().map_err(failed)?;
+    Ok(relative.len())
+}
+
+fn read_bundle_manifest(manifest_path: &str) -> io::Result<BundleManifest> {
+    parse_bundle_manifest(io::BufReader::new(File::open(manifest_path)?))
+}
+
+/** The header lines and the files, as written by write_bundle_manifest **/
+fn parse_bundle_manifest<R: BufRead>(manifest: R) -> io::Result<BundleManifest> {
+    let mut header: Vec<(String, String)> = Vec::new();
+    let mut files: Vec<BundleFile> = Vec::new();
+    for line in manifest.lines() {
+        let line = line?;
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        let columns: Vec<&str> = line.splitn(5, '\t').collect();
+        if 5 == columns.len() {
+            files.push(BundleFile {
+                sha256: columns[0].to_string(),
+                size: u64::from_str(columns[1]).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid size in {:?}", line)))?,
+                path: unescape_manifest_path(columns[3]),
+                command: columns[4].to_string(),
+            });
+        } else if let Some((key, value)) = line.split_once(": ") {
+            header.push((key.to_string(), value.to_string()));
+        } else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid MANIFEST line {:?}", line)));
+        }
+    }
+    Ok((header, files))
+}
+
+/** Re-lists the artifact dir into MANIFEST just before it is archived, keeping the header and known producers, adding when it was archived **/
+fn refresh_bundle_manifest(artifact_dir: &str) -> Result<usize, String> {
+    let manifest_path = format!("{}/{}", artifact_dir, BUNDLE_MANIFEST);
+    let (mut header, files) = match read_bundle_manifest(&manifest_path) {
+        Ok(manifest) => manifest,
+        // collect-info didn't finish, what can still be said about the bundle
+        Err(_) => (
+            vec![
+                ("collector_version".to_string(), env::var("collectInfoVersion").unwrap_or("unknown".to_string())),
+                ("host".to_string(), read_trimmed("/proc/sys/kernel/hostname")),
+            ],
+            Vec::new(),
+        ),
+    };
+    header.retain(|(key, _)| "archived" != key);
+    header.push(("archived".to_string(), format_utc(SystemTime::now())));
+    let producers: HashMap<String, String> = files.into_iter().map(|file| (bundle_path(&format!("{}/{}", artifact_dir, file.path)), file.command)).collect();
+    write_bundle_manifest(artifact_dir, &header, &producers)
+}
+
+fn sha256_file(path: &str) -> io::Result<(String, u64)> {
+    let mut file = File::open(path)?;
+    let mut sha256 = Sha256::new();
+    let mut buffer = vec![0; 65536];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if 0 == read {
+            return Ok((sha256.finish(), sha256.length));
+        }
+        sha256.update(&buffer[..read]);
+    }
+}
+
+/** SHA-256 (FIPS 180-4) **/
+struct Sha256 {
+    state: [u32; 8],
+    // input not yet a whole 64 byte block
+    pending: Vec<u8>,
+    length: u64,
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+impl Sha256 {
+    fn new() -> Sha256 {
+        Sha256 { state: [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19], pending: Vec::new(), length: 0 }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.length += bytes.len() as u64;
+        self.pending.extend(bytes);
+        let whole = self.pending.len() - self.pending.len() % 64;
+        for block in self.pending[..whole].chunks(64) {
+            sha256_compress(&mut self.state, block);
+        }
+        self.pending.drain(..whole);
+    }
+
+    /** The lowercase hex digest **/
+    fn finish(&self) -> String {
+        // padded with a 1 bit, zeros, and the length in bits
+        let mut last = self.pending.clone();
+        last.push(0x80);
+        while 56 != last.len() % 64 {
+            last.push(0);
+        }
+        last.extend(&(self.length * 8).to_be_bytes());
+        let mut state = self.state;
+        for block in last.chunks(64) {
+            sha256_compress(&mut state, block);
+        }
+        state.iter().map(|word| format!("{:08x}", word)).collect()
+    }
+}
+
+fn sha256_compress(state: &mut [u32; 8], block: &[u8]) {
+    let mut w = [0u32; 64];
+    for (i, word) in block.chunks(4).enumerate() {
+        w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for i in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let choice = (e & f) ^ (!e & g);
+        let t1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(majority);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+        *word = word.wrapping_add(*value);
+    }
+}
+
+/** The artifact bundle, $artifactDir.tar.gz, is written natively so nodes don't need tar or gzip (BusyBox variants differ)
+ *
+ * `collect-info archive "$artifactDir"` writes the directory as a ustar archive (PAX records for long names and large files),
+ * compressed with DEFLATE inside a gzip member, to $artifactDir.tar.gz.partial.
+ * The archive is then re-read (inflated, checksums and every entry's content compared) before it is renamed into place,
+ * the caller only removes the artifact directory once collect-info exits 0.
+ **/
+fn archive_artifact_dir(artifact_dir: &str) -> Result<(String, Vec<ArchiveEntry>, u64), String> {
+    let dir = Path::new(artifact_dir.trim_end_matches('/'));
+    let name = dir.file_name().and_then(|name| name.to_str()).ok_or(format!("{} is not a directory name", artifact_dir))?;
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", artifact_dir));
+    }
+    let listed = refresh_bundle_manifest(&dir.to_string_lossy())?;
+    println!("Listed {} files with their SHA-256 in {}… OK", listed, BUNDLE_MANIFEST);
+    let archive_path = format!("{}.tar.gz", dir.display());
+    let partial_path = format!("{}.partial", archive_path);
+    let written = write_archive(dir, name, &partial_path).and_then(|entries| {
+        let size = std::fs::metadata(&partial_path).map_err(|error| format!("failed to stat {}: {}", partial_path, error))?.len();
+        verify_archive(&partial_path, &entries)?;
+        Ok((entries, size))
+    });
+    match written {
+        Ok((entries, size)) => {
+            std::fs::rename(&partial_path, &archive_path).map_err(|error| format!("failed to rename {} to {}: {}", partial_path, archive_path, error))?;
+            Ok((archive_path, entries, size))
+        }
+        Err(error) => {
+            std::fs::remove_file(&partial_path).ok();
+            Err(error)
+        }
+    }
+}
+
+/** What was written for each entry, so the re-read archive can be compared against it **/
+#[derive(Debug, PartialEq)]
+struct ArchiveEntry {
+    path: String,
+    // ustar typeflag: b'0' file, b'2' symlink, b'5' directory
+    kind: u8,
+    size: u64,
+    crc: u32,
+}
+
+fn write_archive(dir: &Path, name: &str, archive_path: &str) -> Result<Vec<ArchiveEntry>, String> {
+    let file = File::create(archive_path).map_err(|error| format!("failed to create {}: {}", archive_path, error))?;
+    let mut gzip = GzipWriter::new(io::BufWriter::new(file));
+    let mut entries: Vec<ArchiveEntry> = Vec::new();
+    let mut sources: Vec<(PathBuf, String)> = vec![(dir.to_path_buf(), format!("{}/", name))];
+    list_archive_sources(dir, name, &mut sources);
+    for (source, path) in sources.iter() {
+        match write_archive_entry(&mut gzip, source, path) {
+            Ok(Some(entry)) => entries.push(entry),
+            Ok(None) => println!("WARNING: not archiving {}, not a file, directory or symlink", source.display()),
+            Err(error) => return Err(format!("failed to archive {}: {}", source.display(), error)),
+        }
+    }
+    let finished = gzip.write_all(&[0; 1024]).and_then(|_| {
+        // pad to a whole tar record (20 blocks) as some readers expect
+        let written = gzip.size % 10240;
+        if 0 < written {
+            gzip.write_all(&vec![0; 10240 - written as usize])?;
+        }
+        gzip.finish()
+    });
+    finished
+        .and_then(|writer| writer.into_inner().map_err(|error| error.into_error()))
+        .and_then(|file| file.sync_all())
+        .map_err(|error| format!("failed to write {}: {}", archive_path, error))?;
+    Ok(entries)
+}
+
+/** Sorted (so archives are reproducible) and without following symlinks, they are archived as symlinks **/
+fn list_archive_sources(dir: &Path, prefix: &str, sources: &mut Vec<(PathBuf, String)>) {
+    let mut children: Vec<(PathBuf, String)> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| (entry.path(), entry.file_name().to_string_lossy().to_string())).collect(),
+        Err(error) => {
+            println!("WARNING: not archiving {}, {}", dir.display(), error);
+            return;
+        }
+    };
+    children.sort_by(|a, b| a.1.cmp(&b.1));
+    for (child, child_name) in children {
+        let path = format!("{}/{}", prefix, child_name);
+        if std::fs::symlink_metadata(&child).map(|metadata| metadata.is_dir()).unwrap_or(false) {
+            sources.push((child.clone(), format!("{}/", path)));
+            list_archive_sources(&child, &path, sources);
+        } else {
+            sources.push((child, path));
+        }
+    }
+}
+
+fn write_archive_entry<W: Write>(archive: &mut W, source: &Path, path: &str) -> io::Result<Option<ArchiveEntry>> {
+    let metadata = std::fs::symlink_metadata(source)?;
+    let file_type = metadata.file_type();
+    let (kind, size, link) = if file_type.is_dir() {
+        (b'5', 0, String::new())
+    } else if file_type.is_symlink() {
+        (b'2', 0, std::fs::read_link(source)?.to_string_lossy().to_string())
+    } else if file_type.is_file() {
+        (b'0', metadata.len(), String::new())
+    } else {
+        return Ok(None);
+    };
+    let header = TarHeader {
+        path: path.to_string(),
+        link,
+        kind,
+        size,
+        mode: metadata.permissions().mode() & 0o7777,
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        mtime: metadata.modified().ok().and_then(|time| time.duration_since(UNIX_EPOCH).ok()).map(|since| since.as_secs()).unwrap_or(0),
+    };
+    let pax = header.pax_records();
+    if !pax.is_empty() {
+        let pax_header = TarHeader {
+            path: format!("PaxHeaders/{}", path.trim_end_matches('/').rsplit('/').next().unwrap_or("")),
+            link: String::new(),
+            kind: b'x',
+            size: pax.len() as u64,
+            mode: 0o644,
+            uid: 0,
+            gid: 0,
+            mtime: header.mtime,
+        };
+        archive.write_all(&pax_header.to_block())?;
+        archive.write_all(&pax)?;
+        archive.write_all(&vec![0; tar_padding(pax.len() as u64)])?;
+    }
+    archive.write_all(&header.to_block())?;
+    let mut crc = Crc32::new();
+    if b'0' == kind {
+        // a file still being written to is archived with the size it had when its header was written
+        let mut file = File::open(source)?.take(size);
+        let mut buffer = vec![0; 65536];
+        let mut copied: u64 = 0;
+        loop {
+            let read = file.read(&mut buffer)?;
+            if 0 == read {
+                break;
+            }
+            crc.update(&buffer[..read]);
+            archive.write_all(&buffer[..read])?;
+            copied += read as u64;
+        }
+        if copied < size {
+            let zeros = vec![0; (size - copied) as usize];
+            crc.update(&zeros);
+            archive.write_all(&zeros)?;
+        }
+    }
+    archive.write_all(&vec![0; tar_padding(size)])?;
+    Ok(Some(ArchiveEntry { path: header.path, kind, size, crc: crc.finish() }))
+}
+
+fn tar_padding(size: u64) -> usize {
+    ((512 - size % 512) % 512) as usize
+}
+
+struct TarHeader {
+    path: String,
+    link: String,
+    kind: u8,
+    size: u64,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    mtime: u64,
+}
+
+// largest values that fit the ustar octal fields
+const TAR_MAX_SIZE: u64 = 0o77777777777;
+const TAR_MAX_ID: u32 = 0o7777777;
+
+impl TarHeader {
+    /** ustar splits paths over prefix (155) and name (100) at a '/', None when it doesn't fit **/
+    fn split_path(&self) -> Option<(&str, &str)> {
+        let path = self.path.as_str();
+        if path.len() <= 100 {
+            return Some(("", path));
+        }
+        // the name part can't be empty, so a directory's trailing '/' is not a split point
+        path[..path.len() - 1].match_indices('/').map(|(at, _)| at).find(|&at| at <= 155 && path.len() - at - 1 <= 100).map(|at| (&path[..at], &path[at + 1..]))
+    }
+
+    /** PAX extended header records (RFC-less, see POSIX pax) for what doesn't fit the ustar header **/
+    fn pax_records(&self) -> Vec<u8> {
+        let mut records: Vec<(&str, String)> = Vec::new();
+        if self.split_path().is_none() || !self.path.is_ascii() {
+            records.push(("path", self.path.clone()));
+        }
+        if 100 < self.link.len() || !self.link.is_ascii() {
+            records.push(("linkpath", self.link.clone()));
+        }
+        if TAR_MAX_SIZE < self.size {
+            records.push(("size", self.size.to_string()));
+        }
+        if TAR_MAX_ID < self.uid {
+            records.push(("uid", self.uid.to_string()));
+        }
+        if TAR_MAX_ID < self.gid {
+            records.push(("gid", self.gid.to_string()));
+        }
+        let mut pax: Vec<u8> = Vec::new();
+        for (key, value) in records {
+            // each record is "<length> <key>=<value>\n", where the length includes its own digits
+            let rest = key.len() + value.len() + 3;
+            let mut length = rest + rest.to_string().len();
+            if length.to_string().len() + rest != length {
+                length += 1;
+            }
+            pax.extend(format!("{} {}={}\n", length, key, value).as_bytes());
+        }
+        pax
+    }
+
+    fn to_block(&self) -> [u8; 512] {
+        let mut block = [0u8; 512];
+        // a truncated ustar path is only used by readers without PAX support
+        let (prefix, name) = self.split_path().unwrap_or(("", truncate_at_char(&self.path, 100)));
+        let put = |block: &mut [u8; 512], offset: usize, length: usize, value: &[u8]| {
+            let length = value.len().min(length);
+            block[offset..offset + length].copy_from_slice(&value[..length]);
+        };
+        let octal = |value: u64, width: usize| format!("{:0width$o}\0", value, width = width - 1);
+        put(&mut block, 0, 100, name.as_bytes());
+        put(&mut block, 100, 8, octal(self.mode as u64, 8).as_bytes());
+        put(&mut block, 108, 8, octal(self.uid.min(TAR_MAX_ID) as u64, 8).as_bytes());
+        put(&mut block, 116, 8, octal(self.gid.min(TAR_MAX_ID) as u64, 8).as_bytes());
+        put(&mut block, 124, 12, octal(self.size.min(TAR_MAX_SIZE), 12).as_bytes());
+        put(&mut block, 136, 12, octal(self.mtime.min(TAR_MAX_SIZE), 12).as_bytes());
+        block[156] = self.kind;
+        put(&mut block, 157, 100, truncate_at_char(&self.link, 100).as_bytes());
+        put(&mut block, 257, 8, b"ustar\x0000");
+        put(&mut block, 345, 155, prefix.as_bytes());
+        // the checksum is calculated with its own field as spaces
+        put(&mut block, 148, 8, b"        ");
+        let checksum: u32 = block.iter().map(|&byte| byte as u32).sum();
+        put(&mut block, 148, 8, format!("{:06o}\0 ", checksum).as_bytes());
+        block
+    }
+}
+
+fn truncate_at_char(value: &str, max: usize) -> &str {
+    let mut end = value.len().min(max);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+/** Inflates the archive and reads back every tar entry, which must match what was written **/
+fn verify_archive(archive_path: &str, written: &[ArchiveEntry]) -> Result<(), String> {
+    let file = File::open(archive_path).map_err(|error| format!("failed to re-open {}: {}", archive_path, error))?;
+    let read = read_tar_entries(&mut GzipReader::new(io::BufReader::new(file))).map_err(|error| format!("failed to re-read {}: {}", archive_path, error))?;
+    if let Some((expected, found)) = written.iter().zip(read.iter()).find(|(expected, found)| expected != found) {
+        return Err(format!("{} re-read as {:?}, expected {:?}", archive_path, found, expected));
+    }
+    if written.len() != read.len() {
+        return Err(format!("{} re-read with {} entries, expected {}", archive_path, read.len(), written.len()));
+    }
+    Ok(())
+}
+
+fn read_tar_entries<R: Read>(archive: &mut R) -> io::Result<Vec<ArchiveEntry>> {
+    let mut entries: Vec<ArchiveEntry> = Vec::new();
+    for_each_tar_entry(archive, &mut |path, kind, size, content| {
+        let mut crc = Crc32::new();
+        let mut buffer = vec![0; 65536];
+        loop {
+            let read = content.read(&mut buffer)?;
+            if 0 == read {
+                break;
+            }
+            crc.update(&buffer[..read]);
+        }
+        entries.push(ArchiveEntry { path: path.to_string(), kind, size, crc: crc.finish() });
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/** Reads a ustar (and PAX) archive calling entry with each entry's (path, typeflag, size, content),
+ * PAX headers, and GNU long name ('L') and long link name ('K') entries, are applied not passed on
+ **/
+fn for_each_tar_entry<R: Read, F: FnMut(&str, u8, u64, &mut dyn Read) -> io::Result<()>>(archive: &mut R, entry: &mut F) -> io::Result<()> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut pax: HashMap<String, String> = HashMap::new();
+    let mut long_name: Option<String> = None;
+    let mut block = [0u8; 512];
+    loop {
+        archive.read_exact(&mut block)?;
+        if block.iter().all(|&byte| 0 == byte) {
+            return Ok(());
+        }
+        let field = |offset: usize, length: usize| {
+            let value = &block[offset..offset + length];
+            String::from_utf8_lossy(&value[..value.iter().position(|&byte| 0 == byte).unwrap_or(length)]).to_string()
+        };
+        let octal = |offset: usize, length: usize| u64::from_str_radix(field(offset, length).trim(), 8).map_err(|_| invalid(format!("invalid octal field {:?}", field(offset, length))));
+        let checksum: u64 = block[..148].iter().chain([b' '; 8].iter()).chain(block[156..].iter()).map(|&byte| byte as u64).sum();
+        if checksum != octal(148, 8)? {
+            return Err(invalid(format!("checksum mismatch in the header of {}", field(0, 100))));
+        }
+        let kind = block[156];
+        let prefix = field(345, 155);
+        let mut path = if prefix.is_empty() { field(0, 100) } else { format!("{}/{}", prefix, field(0, 100)) };
+        let mut size = octal(124, 12)?;
+        if b'x' == kind || b'L' == kind || b'K' == kind {
+            let mut content: Vec<u8> = Vec::new();
+            archive.by_ref().take(size).read_to_end(&mut content)?;
+            if (content.len() as u64) < size {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "tar archive truncated"));
+            }
+            if b'x' == kind {
+                for record in String::from_utf8_lossy(&content).split_terminator('\n') {
+                    let (key, value) = record.split_once(' ').and_then(|(_, record)| record.split_once('=')).ok_or(invalid(format!("invalid PAX record {:?}", record)))?;
+                    pax.insert(key.to_string(), value.to_string());
+                }
+            } else if b'L' == kind {
+                // GNU tar's name for the next entry, NUL terminated (link names are not passed on, so 'K' is only skipped)
+                let end = content.iter().position(|&byte| 0 == byte).unwrap_or(content.len());
+                long_name = Some(String::from_utf8_lossy(&content[..end]).to_string());
+            }
+        } else {
+            path = pax.remove("path").or(long_name.take()).unwrap_or(path);
+            size = pax.remove("size").and_then(|size| u64::from_str(&size).ok()).unwrap_or(size);
+            pax.clear();
+            let mut content = archive.by_ref().take(size);
+            entry(&path, kind, size, &mut content)?;
+            // whatever the callback didn't read
+            io::copy(&mut content, &mut io::sink())?;
+            if 0 < content.limit() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "tar archive truncated"));
+            }
+        }
+        archive.read_exact(&mut vec![0; tar_padding(size)])?;
+    }
+}
+
+/** What support needs from every bundle (from each instance in a multi-instance bundle), a trailing '/' is a directory with a non-empty file **/
+const REQUIRED_ARTIFACTS: &[&str] = &["schema.cql", "nodetool/status.txt", "metrics.jmx", "conf/cassandra.yaml", "logs/"];
+
+/** `collect-info verify <bundle.tar.gz|dir>`, run on the bastion before the bundle is encrypted and uploaded
+ *
+ * Re-reads the bundle, checks every file against the MANIFEST's size and SHA-256, and checks the REQUIRED_ARTIFACTS are present and not empty.
+ * Prints the completeness score and what is missing or empty, and returns the exit code:
+ *  EXIT_COMPLETE, EXIT_PARTIAL with required artifacts missing or empty, EXIT_NOTHING_USEFUL with none of them,
+ *  and 1 when the bundle is unreadable or doesn't match its MANIFEST (truncated or edited).
+ **/
+fn verify_bundle(bundle: &str) -> i32 {
+    println!("Verifying {}…", bundle);
+    let read = if Path::new(bundle).is_dir() { read_bundle_dir(bundle) } else { read_bundle_archive(bundle) };
+    let (files, manifest) = match read {
+        Ok(read) => read,
+        Err(error) => {
+            println!("\tFAILED to read {}: {}", bundle, error);
+            return 1;
+        }
+    };
+    println!("\t{} files", files.len());
+
+    let mut corrupt = false;
+    match manifest {
+        None => println!("\tWARNING: no {}, the files' checksums can't be verified", BUNDLE_MANIFEST),
+        Some((header, listed)) => {
+            let value = |key: &str| header.iter().find(|(name, _)| key == name).map(|(_, value)| value.as_str()).unwrap_or("unknown");
+            println!("\t{}: {} files listed, collected by {} on {} ({} to {})", BUNDLE_MANIFEST, listed.len(), value("collector_version"), value("host"), value("collection_start"), value("collection_end"));
+            let mut matching = 0;
+            for file in listed.iter() {
+                match files.get(&file.path) {
+                    None => println!("\t\tmissing: {}", file.path),
+                    Some((_, size)) if *size != file.size => println!("\t\tchanged: {} ({} bytes, listed with {} bytes)", file.path, size, file.size),
+                    Some((sha256, _)) if *sha256 != file.sha256 => println!("\t\tchanged: {} (SHA-256 differs)", file.path),
+                    Some(_) => matching += 1,
+                }
+            }
+            let unlisted: Vec<&String> = files.keys().filter(|path| BUNDLE_MANIFEST != path.as_str() && !listed.iter().any(|file| &file.path == *path)).collect();
+            unlisted.iter().for_each(|path| println!("\t\tnot listed: {}", path));
+            println!("\tchecksums: {} OK, {} missing or changed, {} not listed", matching, listed.len() - matching, unlisted.len());
+            corrupt = matching < listed.len();
+        }
+    }
+
+    // each instance of a multi-instance bundle has its own per-instance artifacts
+    let mut roots: Vec<String> = files.keys().filter_map(|path| path.strip_prefix("instances/")).filter_map(|path| path.split('/').next()).map(|name| format!("instances/{}/", name)).collect();
+    roots.sort();
+    roots.dedup();
+    if roots.is_empty() {
+        roots.push(String::new());
+    }
+    let mut problems: Vec<String> = Vec::new();
+    let mut present = 0;
+    for root in roots.iter() {
+        for required in REQUIRED_ARTIFACTS.iter() {
+            let path = format!("{}{}", root, required);
+            let sizes: Vec<u64> = if path.ends_with('/') {
+                files.iter().filter(|(file, _)| file.starts_with(&path)).map(|(_, (_, size))| *size).collect()
+            } else {
+                files.get(&path).map(|(_, size)| vec![*size]).unwrap_or_default()
+            };
+            if sizes.is_empty() {
+                problems.push(format!("{} (missing)", path));
+            } else if sizes.iter().all(|&size| 0 == size) {
+                problems.push(format!("{} (empty)", path));
+            } else {
+                present += 1;
+            }
+        }
+    }
+    let required = roots.len() * REQUIRED_ARTIFACTS.len();
+    println!("Completeness: {}% ({} of {} required artifacts)", 100 * present / required, present, required);
+    if !problems.is_empty() {
+        println!("Missing or empty:");
+        problems.iter().for_each(|problem| println!("\t{}", problem));
+    }
+    if corrupt {
+        println!("FAILED: {} does not match its {}", bundle, BUNDLE_MANIFEST);
+        1
+    } else if 0 == present {
+        EXIT_NOTHING_USEFUL
+    } else if present < required {
+        EXIT_PARTIAL
+    } else {
+        EXIT_COMPLETE
+    }
+}
+
+// each file's (SHA-256, size) by its path in the bundle, and the MANIFEST's header and files when there is one
+type BundleContent = (HashMap<String, (String, u64)>, Option<BundleManifest>);
+
+fn read_bundle_dir(dir: &str) -> io::Result<BundleContent> {
+    let root = Path::new(dir);
+    let mut paths: Vec<PathBuf> = Vec::new();
+    list_files(root, &mut paths);
+    let mut files: HashMap<String, (String, u64)> = HashMap::new();
+    for path in paths.iter() {
+        let relative = path.strip_prefix(root).map(|relative| relative.to_string_lossy().to_string()).unwrap_or_default();
+        files.insert(relative, sha256_file(&path.to_string_lossy())?);
+    }
+    let manifest_path = root.join(BUNDLE_MANIFEST);
+    let manifest = if manifest_path.is_file() { Some(read_bundle_manifest(&manifest_path.to_string_lossy())?) } else { None };
+    Ok((files, manifest))
+}
+
+/** The archive's paths are relative to its top directory, the artifact dir **/
+fn read_bundle_archive(archive_path: &str) -> io::Result<BundleContent> {
+    let mut archive = GzipReader::new(io::BufReader::new(File::open(archive_path)?));
+    let mut files: HashMap<String, (String, u64)> = HashMap::new();
+    let mut manifest: Option<Vec<u8>> = None;
+    for_each_tar_entry(&mut archive, &mut |path, kind, size, content| {
+        // regular files, old archives use NUL for them
+        if b'0' != kind && 0 != kind {
+            return Ok(());
+        }
+        let relative = path.split_once('/').map(|(_, relative)| relative).unwrap_or(path).to_string();
+        let mut sha256 = Sha256::new();
+        let mut buffer = vec![0; 65536];
+        let mut kept: Vec<u8> = Vec::new();
+        loop {
+            let read = content.read(&mut buffer)?;
+            if 0 == read {
+                break;
+            }
+            sha256.update(&buffer[..read]);
+            if BUNDLE_MANIFEST == relative {
+                kept.extend(&buffer[..read]);
+            }
+        }
+        if BUNDLE_MANIFEST == relative {
+            manifest = Some(kept);
+        }
+        files.insert(relative, (sha256.finish(), size));
+        Ok(())
+    })?;
+    let manifest = match manifest {
+        Some(content) => Some(parse_bundle_manifest(&content[..])?),
+        None => None,
+    };
+    Ok((files, manifest))
+}
+
+/** CRC-32 (IEEE) as used by gzip and zip **/
+struct Crc32 {
+    table: [u32; 256],
+    value: u32,
+}
+
+impl Crc32 {
+    fn new() -> Crc32 {
+        let mut table = [0u32; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if 1 == c & 1 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+            }
+            *entry = c;
+        }
+        Crc32 { table, value: 0xffffffff }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.value = self.table[((self.value ^ byte as u32) & 0xff) as usize] ^ (self.value >> 8);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        self.value ^ 0xffffffff
+    }
+}
+
+/** DEFLATE (RFC 1951) tables, shared by the compressor and the decompressor
+ * lengths 3-258 are codes 257-285 and distances 1-32768 are codes 0-29, each followed by extra bits
+ **/
+const LENGTH_BASES: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA_BITS: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASES: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+// the order code length code lengths are stored in a dynamic block's header
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+const DEFLATE_WINDOW: usize = 32768;
+// input compressed per block, each block gets its own Huffman codes
+const DEFLATE_BLOCK: usize = 131072;
+// how many earlier matches are tried, and the match length that's good enough to stop trying
+const DEFLATE_MAX_CHAIN: usize = 32;
+const DEFLATE_NICE_LENGTH: usize = 128;
+const DEFLATE_HASH_BITS: u32 = 15;
+
+/** gzip (RFC 1952) compressing writer: LZ77 with hash chains, then a dynamic Huffman (or stored when smaller) block per DEFLATE_BLOCK **/
+struct GzipWriter<W: Write> {
+    inner: W,
+    // the last DEFLATE_WINDOW bytes already compressed, followed by the bytes still to compress
+    window: Vec<u8>,
+    pending: usize,
+    bits: BitWriter,
+    crc: Crc32,
+    size: u64,
+}
+
+impl<W: Write> GzipWriter<W> {
+    fn new(inner: W) -> GzipWriter<W> {
+        let mut bits = BitWriter { bytes: Vec::new(), bits: 0, count: 0 };
+        let mtime = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs() as u32).unwrap_or(0);
+        // magic, deflate, no flags, mtime, no extra flags, unix (written out with the first block)
+        bits.bytes.extend(&[0x1f, 0x8b, 8, 0]);
+        bits.bytes.extend(&mtime.to_le_bytes());
+        bits.bytes.extend(&[0, 3]);
+        GzipWriter { inner, window: Vec::new(), pending: 0, bits, crc: Crc32::new(), size: 0 }
+    }
+
+    fn compress_block(&mut self, end: usize, last: bool) -> io::Result<()> {
+        let tokens = lz77_tokens(&self.window, self.pending, end);
+        write_deflate_block(&mut self.bits, &tokens, &self.window[self.pending..end], last);
+        self.inner.write_all(&self.bits.bytes)?;
+        self.bits.bytes.clear();
+        let keep_from = end.saturating_sub(DEFLATE_WINDOW);
+        self.window.drain(..keep_from);
+        self.pending = end - keep_from;
+        Ok(())
+    }
+
+    /** Compresses what is left as the final block and writes the gzip trailer, returning the inner writer **/
+    fn finish(mut self) -> io::Result<W> {
+        let end = self.window.len();
+        self.compress_block(end, true)?;
+        self.bits.align();
+        self.bits.bytes.extend(&self.crc.finish().to_le_bytes());
+        self.bits.bytes.extend(&(self.size as u32).to_le_bytes());
+        self.inner.write_all(&self.bits.bytes)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for GzipWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.window.extend(buf);
+        self.crc.update(buf);
+        self.size += buf.len() as u64;
+        while DEFLATE_BLOCK <= self.window.len() - self.pending {
+            let end = self.pending + DEFLATE_BLOCK;
+            self.compress_block(end, false)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/** Bits are packed into bytes from the least significant bit, Huffman codes are written reversed (see huffman_codes) **/
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= (value as u64) << self.count;
+        self.count += count;
+        while 8 <= self.count {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn align(&mut self) {
+        if 0 < self.count {
+            self.bytes.push(self.bits as u8);
+            self.bits = 0;
+            self.count = 0;
+        }
+    }
+}
+
+/** Greedy LZ77 over window[start..end], matches may refer back into the DEFLATE_WINDOW bytes before start
+ * each token is (literal byte, 0) or (length, distance)
+ **/
+fn lz77_tokens(window: &[u8], start: usize, end: usize) -> Vec<(u16, u16)> {
+    let hash = |at: usize| ((window[at] as usize) << 10 ^ (window[at + 1] as usize) << 5 ^ window[at + 2] as usize) & ((1 << DEFLATE_HASH_BITS) - 1);
+    let mut head: Vec<usize> = vec![usize::MAX; 1 << DEFLATE_HASH_BITS];
+    let mut previous: Vec<usize> = vec![usize::MAX; end];
+    let insert = |at: usize, head: &mut Vec<usize>, previous: &mut Vec<usize>| {
+        if at + 3 <= end {
+            let h = hash(at);
+            previous[at] = head[h];
+            head[h] = at;
+        }
+    };
+    for at in start.saturating_sub(DEFLATE_WINDOW)..start {
+        insert(at, &mut head, &mut previous);
+    }
+    let mut tokens: Vec<(u16, u16)> = Vec::with_capacity(end - start);
+    let mut at = start;
+    while at < end {
+        let max_length = (end - at).min(258);
+        let (mut best_length, mut best_distance) = (0, 0);
+        if 3 <= max_length {
+            let mut candidate = head[hash(at)];
+            let mut chain = 0;
+            while candidate != usize::MAX && at - candidate <= DEFLATE_WINDOW && chain < DEFLATE_MAX_CHAIN {
+                if window[candidate + best_length] == window[at + best_length] || 0 == best_length {
+                    let length = window[candidate..candidate + max_length].iter().zip(window[at..at + max_length].iter()).take_while(|(a, b)| a == b).count();
+                    if best_length < length {
+                        best_length = length;
+                        best_distance = at - candidate;
+                        if DEFLATE_NICE_LENGTH <= length || max_length == length {
+                            break;
+                        }
+                    }
+                }
+                candidate = previous[candidate];
+                chain += 1;
+            }
+        }
+        if 3 <= best_length {
+            tokens.push((best_length as u16, best_distance as u16));
+            for position in at..at + best_length {
+                insert(position, &mut head, &mut previous);
+            }
+            at += best_length;
+        } else {
+            tokens.push((window[at] as u16, 0));
+            insert(at, &mut head, &mut previous);
+            at += 1;
+        }
+    }
+    tokens
+}
+
+fn length_code(length: u16) -> usize {
+    LENGTH_BASES.iter().rposition(|&base| base <= length).unwrap()
+}
+
+fn distance_code(distance: u16) -> usize {
+    DISTANCE_BASES.iter().rposition(|&base| base <= distance).unwrap()
+}
+
+fn write_deflate_block(bits: &mut BitWriter, tokens: &[(u16, u16)], raw: &[u8], last: bool) {
+    let mut litlen_frequencies = [0u32; 286];
+    let mut distance_frequencies = [0u32; 30];
+    for &(value, distance) in tokens {
+        if 0 == distance {
+            litlen_frequencies[value as usize] += 1;
+        } else {
+            litlen_frequencies[257 + length_code(value)] += 1;
+            distance_frequencies[distance_code(distance)] += 1;
+        }
+    }
+    litlen_frequencies[256] = 1;
+    let litlen_lengths = huffman_lengths(&litlen_frequencies, 15);
+    let distance_lengths = huffman_lengths(&distance_frequencies, 15);
+    let litlen_count = usize::max(257, litlen_lengths.iter().rposition(|&length| 0 < length).unwrap_or(0) + 1);
+    let distance_count = usize::max(1, distance_lengths.iter().rposition(|&length| 0 < length).unwrap_or(0) + 1);
+
+    // both code lengths are themselves run length encoded, and Huffman coded
+    let lengths: Vec<u8> = litlen_lengths[..litlen_count].iter().chain(distance_lengths[..distance_count].iter()).cloned().collect();
+    let runs = code_length_runs(&lengths);
+    let mut code_length_frequencies = [0u32; 19];
+    runs.iter().for_each(|&(symbol, _)| code_length_frequencies[symbol as usize] += 1);
+    let code_length_lengths = huffman_lengths(&code_length_frequencies, 7);
+    let code_length_count = usize::max(4, CODE_LENGTH_ORDER.iter().rposition(|&symbol| 0 < code_length_lengths[symbol]).unwrap_or(0) + 1);
+
+    let extra_bits = |symbol: u8| match symbol {
+        16 => 2,
+        17 => 3,
+        18 => 7,
+        _ => 0,
+    };
+    let mut dynamic_bits: usize = 3 + 5 + 5 + 4 + 3 * code_length_count + litlen_lengths[256] as usize;
+    dynamic_bits += runs.iter().map(|&(symbol, _)| code_length_lengths[symbol as usize] as usize + extra_bits(symbol)).sum::<usize>();
+    for &(value, distance) in tokens {
+        dynamic_bits += if 0 == distance {
+            litlen_lengths[value as usize] as usize
+        } else {
+            let (length, distance) = (length_code(value), distance_code(distance));
+            (litlen_lengths[257 + length] + LENGTH_EXTRA_BITS[length] + distance_lengths[distance] + DISTANCE_EXTRA_BITS[distance]) as usize
+        };
+    }
+    // already compressed content (eg rotated logs) is better stored
+    let stored_bits = (raw.len() + 5 * (1 + raw.len() / 65535)) * 8 + 7;
+    if stored_bits <= dynamic_bits {
+        let mut chunks = raw.chunks(65535).peekable();
+        if chunks.peek().is_none() {
+            write_stored_block(bits, &[], last);
+        }
+        while let Some(chunk) = chunks.next() {
+            write_stored_block(bits, chunk, last && chunks.peek().is_none());
+        }
+        return;
+    }
+
+    let litlen_codes = huffman_codes(&litlen_lengths);
+    let distance_codes = huffman_codes(&distance_lengths);
+    let code_length_codes = huffman_codes(&code_length_lengths);
+    bits.write(last as u32, 1);
+    bits.write(2, 2);
+    bits.write((litlen_count - 257) as u32, 5);
+    bits.write((distance_count - 1) as u32, 5);
+    bits.write((code_length_count - 4) as u32, 4);
+    for &symbol in CODE_LENGTH_ORDER[..code_length_count].iter() {
+        bits.write(code_length_lengths[symbol] as u32, 3);
+    }
+    for &(symbol, extra) in runs.iter() {
+        bits.write(code_length_codes[symbol as usize] as u32, code_length_lengths[symbol as usize] as u32);
+        bits.write(extra as u32, extra_bits(symbol) as u32);
+    }
+    for &(value, distance) in tokens {
+        if 0 == distance {
+            bits.write(litlen_codes[value as usize] as u32, litlen_lengths[value as usize] as u32);
+        } else {
+            let length = length_code(value);
+            bits.write(litlen_codes[257 + length] as u32, litlen_lengths[257 + length] as u32);
+            bits.write((value - LENGTH_BASES[length]) as u32, LENGTH_EXTRA_BITS[length] as u32);
+            let code = distance_code(distance);
+            bits.write(distance_codes[code] as u32, distance_lengths[code] as u32);
+            bits.write((distance - DISTANCE_BASES[code]) as u32, DISTANCE_EXTRA_BITS[code] as u32);
+        }
+    }
+    bits.write(litlen_codes[256] as u32, litlen_lengths[256] as u32);
+}
+
+fn write_stored_block(bits: &mut BitWriter, chunk: &[u8], last: bool) {
+    bits.write(last as u32, 1);
+    bits.write(0, 2);
+    bits.align();
+    bits.bytes.extend(&(chunk.len() as u16).to_le_bytes());
+    bits.bytes.extend(&(!(chunk.len() as u16)).to_le_bytes());
+    bits.bytes.extend(chunk);
+}
+
+/** Code lengths as (symbol, extra bits value), 16 repeats the previous length 3-6 times, 17 and 18 are 3-10 and 11-138 zeros **/
+fn code_length_runs(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut runs: Vec<(u8, u8)> = Vec::new();
+    let mut i = 0;
+    while i < lengths.len() {
+        let length = lengths[i];
+        let run = lengths[i..].iter().take_while(|&&other| other == length).count();
+        if 0 == length && 3 <= run {
+            let zeros = run.min(138);
+            runs.push(if 11 <= zeros { (18, (zeros - 11) as u8) } else { (17, (zeros - 3) as u8) });
+            i += zeros;
+        } else if 0 != length && 4 <= run {
+            let repeats = (run - 1).min(6);
+            runs.push((length, 0));
+            runs.push((16, (repeats - 3) as u8));
+            i += 1 + repeats;
+        } else {
+            runs.push((length, 0));
+            i += 1;
+        }
+    }
+    runs
+}
+
+/** Huffman code lengths no longer than limit, frequencies are halved until the tree is shallow enough
+ * at least two symbols get a code, as not all decoders accept a single one
+ **/
+fn huffman_lengths(frequencies: &[u32], limit: u8) -> Vec<u8> {
+    let mut weights: Vec<u32> = frequencies.to_vec();
+    let mut used = weights.iter().filter(|&&weight| 0 < weight).count();
+    for weight in weights.iter_mut() {
+        if used < 2 && 0 == *weight {
+            *weight = 1;
+            used += 1;
+        }
+    }
+    loop {
+        // leaves first, then each merged node, with its parent
+        let mut parents: Vec<usize> = vec![usize::MAX; weights.len()];
+        let mut heap: std::collections::BinaryHeap<std::cmp::Reverse<(u32, usize)>> =
+            weights.iter().enumerate().filter(|&(_, &weight)| 0 < weight).map(|(symbol, &weight)| std::cmp::Reverse((weight, symbol))).collect();
+        while 1 < heap.len() {
+            let std::cmp::Reverse((a_weight, a)) = heap.pop().unwrap();
+            let std::cmp::Reverse((b_weight, b)) = heap.pop().unwrap();
+            let node = parents.len();
+            parents.push(usize::MAX);
+            parents[a] = node;
+            parents[b] = node;
+            heap.push(std::cmp::Reverse((a_weight + b_weight, node)));
+        }
+        let lengths: Vec<u8> = (0..weights.len())
+            .map(|symbol| {
+                let mut depth = 0;
+                let mut node = symbol;
+                while 0 < weights[symbol] && parents[node] != usize::MAX {
+                    node = parents[node];
+                    depth += 1;
+                }
+                depth
+            })
+            .collect();
+        if lengths.iter().all(|&length| length <= limit) {
+            return lengths;
+        }
+        weights.iter_mut().filter(|weight| 0 < **weight).for_each(|weight| *weight = (*weight).div_ceil(2));
+    }
+}
+
+/** Canonical Huffman codes for the lengths, bit reversed as DEFLATE writes codes from their most significant bit **/
+fn huffman_codes(lengths: &[u8]) -> Vec<u16> {
+    let mut counts = [0u16; 16];
+    lengths.iter().filter(|&&length| 0 < length).for_each(|&length| counts[length as usize] += 1);
+    let mut next = [0u16; 16];
+    for bits in 1..16 {
+        next[bits] = (next[bits - 1] + counts[bits - 1]) << 1;
+    }
+    lengths
+        .iter()
+        .map(|&length| {
+            if 0 == length {
+                return 0;
+            }
+            let code = next[length as usize];
+            next[length as usize] += 1;
+            code.reverse_bits() >> (16 - length)
+        })
+        .collect()
+}
+
+/** gzip (RFC 1952) decompressing reader, multiple members are read as one stream and each member's CRC and size are checked **/
+struct GzipReader<R: BufRead> {
+    inflater: Option<Inflater<R>>,
+    input: Option<R>,
+    crc: Crc32,
+    size: u64,
+}
+
+impl<R: BufRead> GzipReader<R> {
+    fn new(input: R) -> GzipReader<R> {
+        GzipReader { inflater: None, input: Some(input), crc: Crc32::new(), size: 0 }
+    }
+
+    /** Reads the next member's header, false at the end of the input **/
+    fn start_member(&mut self) -> io::Result<bool> {
+        let mut input = self.input.take().unwrap();
+        if input.fill_buf()?.is_empty() {
+            self.input = Some(input);
+            return Ok(false);
+        }
+        let mut header = [0u8; 10];
+        input.read_exact(&mut header)?;
+        if 0x1f != header[0] || 0x8b != header[1] || 8 != header[2] {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not gzip (deflate) content"));
+        }
+        let flags = header[3];
+        if 0 != flags & 4 {
+            let mut length = [0u8; 2];
+            input.read_exact(&mut length)?;
+            io::copy(&mut (&mut input).take(u16::from_le_bytes(length) as u64), &mut io::sink())?;
+        }
+        // the original file name and comment, both zero terminated
+        for flag in [8, 16].iter() {
+            if 0 != flags & flag {
+                input.read_until(0, &mut Vec::new())?;
+            }
+        }
+        if 0 != flags & 2 {
+            input.read_exact(&mut [0u8; 2])?;
+        }
+        self.inflater = Some(Inflater::new(input));
+        self.crc = Crc32::new();
+        self.size = 0;
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> Read for GzipReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.inflater.is_none() && !self.start_member()? {
+                return Ok(0);
+            }
+            let inflater = self.inflater.as_mut().unwrap();
+            let read = inflater.read(buf)?;
+            if 0 < read || buf.is_empty() {
+                self.crc.update(&buf[..read]);
+                self.size += read as u64;
+                return Ok(read);
+            }
+            let mut trailer = [0u8; 8];
+            let mut input = self.inflater.take().unwrap().into_trailer(&mut trailer)?;
+            if self.crc.finish().to_le_bytes() != trailer[..4] || (self.size as u32).to_le_bytes() != trailer[4..] {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "gzip checksum mismatch"));
+            }
+            // some writers pad the end with zeros
+            while input.fill_buf()?.first() == Some(&0) {
+                input.consume(1);
+            }
+            self.input = Some(input);
+        }
+    }
+}
+
+/** An entry of a zip archive's central directory (APPNOTE.TXT 4.3.12) **/
+struct ZipEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    // of its local file header
+    offset: u64,
+}
+
+fn le16(bytes: &[u8], at: usize) -> u64 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]]) as u64
+}
+
+fn le32(bytes: &[u8], at: usize) -> u64 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as u64
+}
+
+fn le64(bytes: &[u8], at: usize) -> u64 {
+    le32(bytes, at) | le32(bytes, at + 4) << 32
+}
+
+/** Lists the zip's entries from its central directory, found through the end of central directory record (or the zip64 one) **/
+fn read_zip_directory(file: &mut File) -> io::Result<Vec<ZipEntry>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let length = file.metadata()?.len();
+    // the end record (22 bytes) ends with a comment of up to 65535 bytes
+    file.seek(io::SeekFrom::Start(length.saturating_sub(22 + 65535)))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    let end = (0..tail.len().saturating_sub(21)).rev().find(|&at| tail[at..at + 4] == [0x50, 0x4b, 0x05, 0x06]).ok_or_else(|| invalid("not a zip (no end of central directory)"))?;
+    let (mut count, mut directory_size, mut directory_offset) = (le16(&tail, end + 10), le32(&tail, end + 12), le32(&tail, end + 16));
+    // zip64: the locator, just before the end record, points to the zip64 end record
+    if 20 <= end && tail[end - 20..end - 16] == [0x50, 0x4b, 0x06, 0x07] {
+        file.seek(io::SeekFrom::Start(le64(&tail, end - 12)))?;
+        let mut record = [0u8; 56];
+        file.read_exact(&mut record)?;
+        if record[..4] != [0x50, 0x4b, 0x06, 0x06] {
+            return Err(invalid("bad zip64 end of central directory"));
+        }
+        count = le64(&record, 32);
+        directory_size = le64(&record, 40);
+        directory_offset = le64(&record, 48);
+    }
+    // the offset and size of a corrupt (zip64) record can be anything
+    if directory_offset.checked_add(directory_size).is_none_or(|directory_end| length < directory_end) {
+        return Err(invalid("zip central directory past the end of the file (truncated)"));
+    }
+    file.seek(io::SeekFrom::Start(directory_offset))?;
+    let mut directory = vec![0; directory_size as usize];
+    file.read_exact(&mut directory)?;
+    let mut entries = Vec::new();
+    let mut at = 0;
+    for _ in 0..count {
+        if directory.len() < at + 46 || directory[at..at + 4] != [0x50, 0x4b, 0x01, 0x02] {
+            return Err(invalid("bad zip central directory"));
+        }
+        let name_end = at + 46 + le16(&directory, at + 28) as usize;
+        let extra_end = name_end + le16(&directory, at + 30) as usize;
+        let next = extra_end + le16(&directory, at + 32) as usize;
+        if directory.len() < next {
+            return Err(invalid("bad zip central directory"));
+        }
+        let mut entry = ZipEntry {
+            name: String::from_utf8_lossy(&directory[at + 46..name_end]).to_string(),
+            method: le16(&directory, at + 10) as u16,
+            crc: le32(&directory, at + 16) as u32,
+            compressed_size: le32(&directory, at + 20),
+            size: le32(&directory, at + 24),
+            offset: le32(&directory, at + 42),
+        };
+        // the zip64 extra field holds (in this order) only the values that didn't fit
+        let mut extra = name_end;
+        while extra + 4 <= extra_end {
+            let field_end = (extra + 4 + le16(&directory, extra + 2) as usize).min(extra_end);
+            if 1 == le16(&directory, extra) {
+                let mut field = extra + 4;
+                for value in [&mut entry.size, &mut entry.compressed_size, &mut entry.offset].iter_mut() {
+                    if 0xffff_ffff == **value && field + 8 <= field_end {
+                        **value = le64(&directory, field);
+                        field += 8;
+                    }
+                }
+            }
+            extra = field_end;
+        }
+        entries.push(entry);
+        at = next;
+    }
+    Ok(entries)
+}
 
-    check_all_commands(&options);
-    execute_all_commands(&options);
+/** A zip entry's content (stored or deflated), decompressed while read, its CRC and size checked at the end **/
+struct ZipEntryReader {
+    content: Box<dyn Read>,
+    crc: Crc32,
+    size: u64,
+    expected_crc: u32,
+    expected_size: u64,
 }
 
-fn check_all_commands(options: &Options) {
-    println!("Checking commands required to collect information…");
+impl ZipEntryReader {
+    fn open(path: &Path, entry: &ZipEntry) -> io::Result<ZipEntryReader> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut file = File::open(path)?;
+        file.seek(io::SeekFrom::Start(entry.offset))?;
+        let mut header = [0u8; 30];
+        file.read_exact(&mut header)?;
+        if header[..4] != [0x50, 0x4b, 0x03, 0x04] {
+            return Err(invalid(format!("bad zip local header for {}", entry.name)));
+        }
+        // the local name and extra field can differ from the central directory's
+        file.seek(io::SeekFrom::Current((le16(&header, 26) + le16(&header, 28)) as i64))?;
+        let data = io::BufReader::new(file.take(entry.compressed_size));
+        let content: Box<dyn Read> = match entry.method {
+            0 => Box::new(data),
+            8 => Box::new(Inflater::new(data)),
+            method => return Err(invalid(format!("unsupported zip compression method {} for {}", method, entry.name))),
+        };
+        Ok(ZipEntryReader { content, crc: Crc32::new(), size: 0, expected_crc: entry.crc, expected_size: entry.size })
+    }
+}
 
-    let mut checked_commands = HashSet::new();
-    COMMANDS.iter().enumerate().for_each(|(_i, cmd)| {
+impl Read for ZipEntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.content.read(buf)?;
+        self.crc.update(&buf[..read]);
+        self.size += read as u64;
+        if 0 == read && !buf.is_empty() && (self.crc.finish() != self.expected_crc || self.size != self.expected_size) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "zip entry checksum mismatch (truncated or corrupt)"));
+        }
+        Ok(read)
+    }
+}
 
-        let cmd_str = format_command(cmd.command, options);
-        if !checked_commands.contains(&cmd_str) {
-            checked_commands.insert(cmd_str.clone());
+/** Streaming INFLATE (RFC 1951), decodes into a window of the last DEFLATE_WINDOW bytes plus what is yet to be read **/
+struct Inflater<R: BufRead> {
+    bits: BitReader<R>,
+    window: Vec<u8>,
+    position: usize,
+    block: InflateBlock,
+    last: bool,
+}
 
-            let sudo_str = (if cmd.use_sudo && !options.skip_sudo { "sudo " } else { "" }).to_string();
+enum InflateBlock {
+    Header,
+    Stored(usize),
+    Huffman(HuffmanTable, HuffmanTable),
+    Done,
+}
 
-            print!("\tlooking for `{}{}`… ", sudo_str, cmd_str);
-            io::stdout().flush().ok().expect("Could not flush stdout");
+impl<R: BufRead> Inflater<R> {
+    fn new(input: R) -> Inflater<R> {
+        Inflater { bits: BitReader { input, bits: 0, count: 0 }, window: Vec::new(), position: 0, block: InflateBlock::Header, last: false }
+    }
 
-            let result = check_command(cmd, &options);
-            if result.0 {
-                println!("FOUND at {}", result.1.replace("\n", ""));
-            } else {
-                println!("missing");
-                if !cmd.optional {
-                    println!(
-                        "FATAL: {} not found and is not optional for the collector",
-                        cmd_str
-                    )
+    /** Once fully read, the remaining input with the 8 bytes following the deflate stream (the gzip trailer) read into trailer **/
+    fn into_trailer(mut self, trailer: &mut [u8]) -> io::Result<R> {
+        self.bits.align()?;
+        for byte in trailer.iter_mut() {
+            *byte = self.bits.read(8)? as u8;
+        }
+        Ok(self.bits.input)
+    }
+
+    /** Decodes until at least DEFLATE_WINDOW bytes are available to read, or the stream ends **/
+    fn fill(&mut self) -> io::Result<()> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        while self.window.len() - self.position < DEFLATE_WINDOW {
+            match self.block {
+                InflateBlock::Done => return Ok(()),
+                InflateBlock::Header => {
+                    if self.last {
+                        self.block = InflateBlock::Done;
+                        continue;
+                    }
+                    self.last = 1 == self.bits.read(1)?;
+                    self.block = match self.bits.read(2)? {
+                        0 => {
+                            self.bits.align()?;
+                            let length = self.bits.read(16)?;
+                            if length != !self.bits.read(16)? & 0xffff {
+                                return Err(invalid("stored block length mismatch"));
+                            }
+                            InflateBlock::Stored(length as usize)
+                        }
+                        1 => {
+                            let litlen: Vec<u8> = (0..288).map(|symbol| if symbol < 144 { 8 } else if symbol < 256 { 9 } else if symbol < 280 { 7 } else { 8 }).collect();
+                            InflateBlock::Huffman(HuffmanTable::new(&litlen)?, HuffmanTable::new(&[5; 30])?)
+                        }
+                        2 => self.read_dynamic_tables()?,
+                        _ => return Err(invalid("invalid block type")),
+                    };
+                }
+                InflateBlock::Stored(0) => self.block = InflateBlock::Header,
+                InflateBlock::Stored(ref mut remaining) => {
+                    let byte = self.bits.read(8)? as u8;
+                    self.window.push(byte);
+                    *remaining -= 1;
+                }
+                InflateBlock::Huffman(ref litlen, ref distance) => {
+                    let symbol = litlen.decode(&mut self.bits)?;
+                    if symbol < 256 {
+                        self.window.push(symbol as u8);
+                    } else if 256 == symbol {
+                        self.block = InflateBlock::Header;
+                    } else if symbol < 286 {
+                        let code = symbol as usize - 257;
+                        let length = LENGTH_BASES[code] as usize + self.bits.read(LENGTH_EXTRA_BITS[code] as u32)? as usize;
+                        let code = distance.decode(&mut self.bits)? as usize;
+                        if 30 <= code {
+                            return Err(invalid("invalid distance code"));
+                        }
+                        let distance = DISTANCE_BASES[code] as usize + self.bits.read(DISTANCE_EXTRA_BITS[code] as u32)? as usize;
+                        if self.window.len() < distance {
+                            return Err(invalid("distance too far back"));
+                        }
+                        // the copy may overlap what it is copying
+                        let from = self.window.len() - distance;
+                        for at in from..from + length {
+                            let byte = self.window[at];
+                            self.window.push(byte);
+                        }
+                    } else {
+                        return Err(invalid("invalid literal/length code"));
+                    }
                 }
             }
         }
-    });
-    println!(" …OK");
-}
+        Ok(())
+    }
 
-fn execute_all_commands(options: &Options) {
-    println!("Collecting OS information… ");
+    fn read_dynamic_tables(&mut self) -> io::Result<InflateBlock> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let litlen_count = self.bits.read(5)? as usize + 257;
+        let distance_count = self.bits.read(5)? as usize + 1;
+        let code_length_count = self.bits.read(4)? as usize + 4;
+        let mut code_length_lengths = [0u8; 19];
+        for &symbol in CODE_LENGTH_ORDER[..code_length_count].iter() {
+            code_length_lengths[symbol] = self.bits.read(3)? as u8;
+        }
+        let code_lengths = HuffmanTable::new(&code_length_lengths)?;
+        let mut lengths: Vec<u8> = Vec::with_capacity(litlen_count + distance_count);
+        while lengths.len() < litlen_count + distance_count {
+            let (length, repeat) = match code_lengths.decode(&mut self.bits)? {
+                16 => (*lengths.last().ok_or(invalid("repeat without a previous length"))?, 3 + self.bits.read(2)?),
+                17 => (0, 3 + self.bits.read(3)?),
+                18 => (0, 11 + self.bits.read(7)?),
+                length => (length as u8, 1),
+            };
+            lengths.extend(std::iter::repeat_n(length, repeat as usize));
+        }
+        if litlen_count + distance_count < lengths.len() {
+            return Err(invalid("too many code lengths"));
+        }
+        Ok(InflateBlock::Huffman(HuffmanTable::new(&lengths[..litlen_count])?, HuffmanTable::new(&lengths[litlen_count..])?))
+    }
+}
 
-    let auditor: File = create_auditor_file(&options);
+impl<R: BufRead> Read for Inflater<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.window.len() {
+            // keep only the history matches can refer back to
+            if 2 * DEFLATE_WINDOW < self.window.len() {
+                let keep_from = self.window.len() - DEFLATE_WINDOW;
+                self.window.drain(..keep_from);
+                self.position -= keep_from;
+            }
+            self.fill()?;
+        }
+        let read = buf.len().min(self.window.len() - self.position);
+        buf[..read].copy_from_slice(&self.window[self.position..self.position + read]);
+        self.position += read;
+        Ok(read)
+    }
+}
 
-    COMMANDS.iter().enumerate().for_each(|(_i, cmd)| {
-        let cmd_str = format_command(cmd.command, options);
-        let args_str = format_args(cmd.args, options, true);
+struct BitReader<R: BufRead> {
+    input: R,
+    bits: u64,
+    count: u32,
+}
 
-        if !cmd.optional || check_command(cmd, &options).0 {
-            print!("\texecuting `{} {} > {}`… ", cmd_str, args_str, cmd.file);
-            io::stdout().flush().ok().expect("Could not flush stdout");
+impl<R: BufRead> BitReader<R> {
+    /** The next count bits without consuming them, zeros past the end of the input **/
+    fn peek(&mut self, count: u32) -> io::Result<u32> {
+        while self.count < count {
+            let byte = match self.input.fill_buf()?.first() {
+                Some(&byte) => byte,
+                None => break,
+            };
+            self.input.consume(1);
+            self.bits |= (byte as u64) << self.count;
+            self.count += 8;
+        }
+        Ok((self.bits & ((1 << count) - 1)) as u32)
+    }
 
-            if !should_skip_command(cmd) {
-                if DRY_RUN || execute_command(cmd, options, &auditor) {
-                    println!("OK");
-                } else {
-                    println!("failed");
-                    assert!(cmd.optional);
-                }
-            } else {
-                println!("skipped");
-            }
-        } else {
-            println!("\tskipping  `{} {}`", cmd_str, args_str);
+    fn consume(&mut self, count: u32) -> io::Result<()> {
+        if self.count < count {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "deflate stream truncated"));
         }
-    });
+        self.bits >>= count;
+        self.count -= count;
+        Ok(())
+    }
 
-    println!(" …OK");
-}
+    fn read(&mut self, count: u32) -> io::Result<u32> {
+        let value = self.peek(count)?;
+        self.consume(count)?;
+        Ok(value)
+    }
 
-fn create_auditor_file(options: &Options) -> File {
-    let auditor_str = &format!("{}/collect-info.audit.log", options.artifact_dir);
-    let auditor_path = Path::new(&auditor_str);
-    std::fs::create_dir_all(auditor_path.parent().unwrap()).unwrap();
-    File::create(&auditor_path).expect("failed to create file collect-info.audit.log")
+    fn align(&mut self) -> io::Result<()> {
+        self.consume(self.count % 8)
+    }
 }
 
-fn should_skip_command(cmd: &Cmd) -> bool {
-    let mut skip = false;
-    for skip_flag in cmd.skip_flags.split_whitespace() {
-        let val = env::var(skip_flag);
-        skip |= val.is_ok() && FromStr::from_str(&val.unwrap()).unwrap();
-    }
-    skip
+/** Decoding table indexed by the next max_length (reversed) bits, each entry the (symbol, code length) **/
+struct HuffmanTable {
+    entries: Vec<(u16, u8)>,
+    max_length: u32,
 }
 
-fn check_command(cmd: &Cmd, options: &Options) -> (bool, String) {
-    let cmd_str = format_command(cmd.command, options);
-    let mut check_cmd = create_check_command(&cmd, &options);
+impl HuffmanTable {
+    fn new(lengths: &[u8]) -> io::Result<HuffmanTable> {
+        let max_length = lengths.iter().cloned().max().unwrap_or(0) as u32;
+        // a complete code uses all of 2^max_length, more than that is invalid (less is tolerated until an unused code is read)
+        let used: u64 = lengths.iter().filter(|&&length| 0 < length).map(|&length| 1u64 << (max_length - length as u32)).sum();
+        if 15 < max_length || (1u64 << max_length) < used {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid Huffman code lengths"));
+        }
+        let mut entries: Vec<(u16, u8)> = vec![(0, 0); 1 << max_length];
+        for (symbol, (&length, &code)) in lengths.iter().zip(huffman_codes(lengths).iter()).enumerate() {
+            if 0 < length {
+                for index in (code as usize..entries.len()).step_by(1 << length) {
+                    entries[index] = (symbol as u16, length);
+                }
+            }
+        }
+        Ok(HuffmanTable { entries, max_length })
+    }
 
-    let result = check_cmd.0
-        .arg("-c")
-        .arg(format!("command -v {}", cmd_str))
-        .output()
-        .expect(format!("failed to execute `{} -c 'command -v {}'`", check_cmd.1, cmd_str).as_str());
+    fn decode<R: BufRead>(&self, bits: &mut BitReader<R>) -> io::Result<u16> {
+        let (symbol, length) = self.entries[bits.peek(self.max_length)? as usize];
+        if 0 == length {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid Huffman code"));
+        }
+        bits.consume(length as u32)?;
+        Ok(symbol)
+    }
+}
 
-    (result.status.success(), std::str::from_utf8(&result.stdout).unwrap().to_string())
+struct Execution {
+    started: SystemTime,
+    ended: SystemTime,
+    status: ExitStatus,
+    timed_out: bool,
+    // the side output files (see output_files) that were not empty
+    captured_files: Vec<String>,
 }
 
-fn create_check_command(cmd: &Cmd, options: &Options) -> (Command, String) {
-  if cmd.use_sudo && !options.skip_sudo {
-    let mut command = Command::new("sudo");
-    command.arg("sh");
-    (command, "sudo sh".to_string())
-  } else {
-    (Command::new("sh"), "sh".to_string())
-  }
+/** Minimal JSON object writer, std only
+ * values are serialised as they are added, call build() for the final `{…}`
+ **/
+struct JsonObject {
+    body: String,
 }
 
-fn execute_command(cmd: &Cmd, options: &Options, mut auditor: &File) -> bool {
-    assert!(!DRY_RUN);
-    let cmd_str = format_command(cmd.command, options);
-    let args_str = format_args(cmd.args, options, true);
+impl JsonObject {
+    fn new() -> JsonObject {
+        JsonObject { body: String::new() }
+    }
 
-    write!(auditor, "{} {} > {}\n", cmd_str, args_str, cmd.file).expect(
-        format!(
-            "failed auditing `{} {} > {}`",
-            cmd_str, args_str, cmd.file
-        )
-        .as_str(),
-    );
+    fn raw(&mut self, key: &str, json: &str) -> &mut JsonObject {
+        if !self.body.is_empty() {
+            self.body.push(',');
+        }
+        self.body.push_str(&json_string(key));
+        self.body.push(':');
+        self.body.push_str(json);
+        self
+    }
 
-    let mut command = create_command(&cmd_str, &cmd, &options);
-    if !cmd.args.is_empty() {
-        command.args(format_args(cmd.args, options, false).split_whitespace());
+    fn string(&mut self, key: &str, value: &str) -> &mut JsonObject {
+        self.raw(key, &json_string(value))
     }
-    assert!(!cmd.file.is_empty() || !cmd.use_sudo, "use_sudo cannot be used when cmd.file is empty (`{} {}`)", cmd_str, args_str);
-    if let Some(file) = create_command_output_file(options.artifact_dir, cmd.file) {
-        if cmd.use_stdout {
-            command.stdout(Stdio::from(file));
-        } else {
-            command.stderr(Stdio::from(file));
+
+    fn string_or_null(&mut self, key: &str, value: Option<&str>) -> &mut JsonObject {
+        match value {
+            Some(value) => self.string(key, value),
+            None => self.raw(key, "null"),
         }
     }
-    command
-        .status()
-        .expect(format!("failed to collect command `{} {}`", cmd_str, args_str).as_str())
-        .success()
-}
 
-fn format_command(cmd: &str, options: &Options) -> String {
-    cmd.replace("{base_dir}", options.base_dir)
-        .replace("{dse_bin_dir}", options.dse_bin_dir)
-}
+    fn number<T: std::fmt::Display>(&mut self, key: &str, value: T) -> &mut JsonObject {
+        self.raw(key, &value.to_string())
+    }
 
-fn format_args(args: &str, options: &Options, mask: bool) -> String {
-    args.replace("{artifact_dir}", options.artifact_dir)
-        .replace("{log_home}", options.log_home)
-        .replace("{logs_max_age_days}", options.logs_max_age_days)
-        .replace("{data_dir}", options.data_dir)
-        .replace("{config_home}", options.config_home)
-        .replace("{cassandra_pid}", options.cassandra_pid)
-        .replace("{prometheus_jar}", options.prometheus_jar)
-        .replace("{jmx_host}", options.jmx_host)
-        .replace("{jmx_port}", options.jmx_port)
-        .replace("{jmx_username}", options.jmx_username)
-        .replace("{jmx_password}", &format_jmx_password(&options.jmx_password, mask))
-        .replace("{jmx_exporter_opts}", options.jmx_exporter_opts)
-        .replace("{nodetool_ssl}", &nodetool_ssl(options.jmx_ssl))
-        .replace("{nodetool_credentials}", &format_jmx_password(&options.nodetool_credentials, mask))
-        .replace("{cqlsh_host}", options.cqlsh_host)
-        .replace("{cqlsh_port}", options.cqlsh_port)
-        .replace("{cqlsh_opts}", &format_cqlsh_opts(&options.cqlsh_opts, options.cqlsh_password, mask))
-        .replace("{dse_conf_dir}", options.dse_conf_dir)
-        .replace("{dt_opts}", options.dt_opts)
-        .replace("{solr_data_dir}", options.solr_data_dir)
-}
-
-fn nodetool_ssl(jmx_ssl: bool) -> String {
-    if jmx_ssl {
-        "--ssl".to_string()
-    } else {
-        "".to_string()
+    fn number_or_null<T: std::fmt::Display>(&mut self, key: &str, value: Option<T>) -> &mut JsonObject {
+        match value {
+            Some(value) => self.number(key, value),
+            None => self.raw(key, "null"),
+        }
     }
-}
 
-fn format_jmx_password(sensitive: &str, mask: bool) -> String {
-    if mask && !sensitive.is_empty() {
-        "****".to_string()
-    } else {
-        sensitive.to_string()
+    fn boolean(&mut self, key: &str, value: bool) -> &mut JsonObject {
+        self.raw(key, if value { "true" } else { "false" })
     }
-}
 
-fn format_cqlsh_opts(cqlsh_opts: &str, cqlsh_password: &str, mask: bool) -> String {
-    if mask {
-        cqlsh_opts.replace(cqlsh_password, "****")
-    } else {
-        cqlsh_opts.to_string()
+    fn build(&self) -> String {
+        format!("{{{}}}", self.body)
     }
 }
 
-fn create_command(cmd_str: &str, cmd: &Cmd, options: &Options) -> Command {
-    if cmd.use_sudo && !options.skip_sudo {
-        let mut command = Command::new("sudo");
-        if cmd.use_timeout {
-            command.arg("timeout");
-            command.args(options.timeout_opts.split_whitespace());
-        }
-        command.arg(cmd_str);
-        command
-    } else {
-        if cmd.use_timeout {
-            let mut command = Command::new("timeout");
-            command.args(options.timeout_opts.split_whitespace());
-            command.arg(cmd_str);
-            command
-        } else {
-            Command::new(cmd_str)
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
         }
     }
+    escaped.push('"');
+    escaped
 }
 
-fn create_command_output_file(artifact_dir: &str, cmd_file: &str) -> Option<File> {
-    if cmd_file.is_empty() {
-        None
-    } else {
-        let path_str: &String = &format!("{}/{}", artifact_dir, cmd_file);
-        let path = Path::new(path_str);
-        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
-        Some(File::create(&path).expect(format!("failed to create file {}", cmd_file).as_str()))
-    }
+fn json_array(items: &[String]) -> String {
+    format!("[{}]", items.join(","))
+}
+
+fn json_string_array(items: &[&str]) -> String {
+    json_array(&items.iter().map(|item| json_string(item)).collect::<Vec<String>>())
+}
+
+/** Formats as ISO-8601 UTC with milliseconds, eg 2024-03-01T14:05:09.123Z **/
+fn format_utc(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let secs_of_day = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year, month, day, secs_of_day / 3600, secs_of_day % 3600 / 60, secs_of_day % 60, since_epoch.subsec_millis()
+    )
+}
+
+// days since 1970-01-01 to (year, month, day), see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+// (year, month, day) to days since 1970-01-01, the inverse of civil_from_days()
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
 }
 
+
+fn duration_millis(start: SystemTime, end: SystemTime) -> u128 {
+    end.duration_since(start).unwrap_or(Duration::from_secs(0)).as_millis()
+}
+
+#[derive(Clone)]
 struct Options<'a> {
     base_dir: &'a str,
     artifact_dir: &'a str,
     skip_sudo: bool,
     log_home: &'a str,
     logs_max_age_days: &'a str,
+    // the native copy of logs/, see copy_tree()
+    logs_include: &'a str,
+    logs_exclude: &'a str,
+    logs_max_file_mb: u64,
+    logs_max_total_mb: u64,
+    logs_truncate: bool,
+    // the incident window extracted into logs-window/, see log_window()
+    logs_window_start: &'a str,
+    logs_window_end: &'a str,
+    logs_window_hours: &'a str,
+    logs_window_only: bool,
+    // logs/summary.txt and logs/summary.json, see native_log_summary()
+    log_summary: bool,
+    // logs/gc-summary.txt, see native_gc_summary()
+    gc_summary: bool,
+    // nodetool/*.json next to the text, see structure_nodetool_output()
+    nodetool_json: bool,
     config_home: &'a str,
     data_dir: &'a str,
     cassandra_pid: &'a str,
@@ -310,19 +6447,42 @@ struct Options<'a> {
     jmx_password: &'a str,
     jmx_ssl: bool,
     jmx_exporter_opts: &'a str,
+    // how the JMX endpoint was decided, see discover_jmx()
+    jmx_trail: &'a [String],
     nodetool_credentials: &'a str,
     cqlsh_host: &'a str,
     cqlsh_port: &'a str,
     cqlsh_opts: &'a str,
+    cqlsh_username: &'a str,
     cqlsh_password: &'a str,
-    timeout_opts: &'a str,
     dse_bin_dir: &'a str,
     dse_conf_dir: &'a str,
     dt_opts: &'a str,
     solr_data_dir: &'a str,
+    default_timeout: u64,
+    max_concurrency: usize,
+    dry_run: bool,
+    // read /proc and /sys directly, see NATIVE_FACTS
+    native_facts: bool,
+    // ids of the commands replaced by native facts
+    natively_collected: HashSet<&'static str>,
+    // sample /proc into os-metrics/ instead of running vmstat, iostat, top and dstat, see sample_os_metrics()
+    native_sampler: bool,
+    sample_duration: u64,
+    sample_interval: u64,
+    // mask secrets in the collected files afterwards, see redact_artifacts()
+    redact: bool,
+    redact_rules: &'a str,
+    // private directory of the Secrets
+    secrets_dir: &'a str,
+    // the java on the path is 9+, and can be given its arguments in an @argfile
+    java_argfiles: bool,
 }
 
+#[derive(Clone)]
 struct Cmd<'a> {
+    // unique, used by the manifest (collectInfoManifest) to replace or disable the command
+    id: &'a str,
     command: &'a str,
     args: &'a str,
     file: &'a str,
@@ -330,12 +6490,18 @@ struct Cmd<'a> {
     skip_flags: &'a str,
     use_stdout: bool,
     use_sudo: bool,
-    use_timeout: bool,
+    // seconds before the command is killed, 0 is the default timeout (collectInfoTimeout)
+    timeout: u64,
+    // exclusive commands never run concurrently with each other, eg they all start a JVM or connect to Cassandra
+    exclusive: bool,
+    // run once for each Cassandra instance on the host (into instances/<name>/), otherwise once for the host
+    per_instance: bool,
 }
 
 const COMMANDS: &[Cmd<'static>] = &[
     // cqlsh "$(hostname)" $cqlshOpts -e 'DESCRIBE SCHEMA;' > "$artifactDir/schema.cql"
     Cmd {
+        id: "schema",
         command: "cqlsh",
         args: "{cqlsh_host} {cqlsh_port} {cqlsh_opts} -f {artifact_dir}/execute_schema.cql",
         file: "schema.cql",
@@ -343,10 +6509,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 120,
+        exclusive: true,
+        per_instance: true,
     },
     // cqlsh "$(hostname)" $cqlshOpts -e 'DESCRIBE CLUSTER;' > "$artifactDir/metadata.cql"
     Cmd {
+        id: "driver.metadata",
         command: "cqlsh",
         args: "{cqlsh_host} {cqlsh_port} {cqlsh_opts} -f {artifact_dir}/execute_metadata.cql",
         file: "driver/metadata.txt",
@@ -354,10 +6523,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     // "java -cp $baseDir/$prometheus io.prometheus.jmx.JmxScraper service:jmx:rmi:///jndi/rmi://127.0.0.1:$jmxPort/jmxrmi $jmxUsername $jmxPassword  > $artifactDir/metrics.jmx"
     Cmd {
+        id: "metrics",
         command: "java",
         args: "{jmx_exporter_opts} -cp {prometheus_jar} io.prometheus.jmx.JmxScraper service:jmx:rmi:///jndi/rmi://{jmx_host}:{jmx_port}/jmxrmi {jmx_username} {jmx_password}",
         file: "metrics.jmx",
@@ -365,10 +6537,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "jmxSSL",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 120,
+        exclusive: true,
+        per_instance: true,
     },
     // ssl version of above
     Cmd {
+        id: "metrics-ssl",
         command: "java",
         args: "{jmx_exporter_opts} -cp {prometheus_jar} io.prometheus.jmx.JmxScraper service:jmx:rmi:///jndi/rmi://{jmx_host}:{jmx_port}/jmxrmi {jmx_username} {jmx_password} ssl",
         file: "metrics.jmx",
@@ -376,10 +6551,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "jmxPlain",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 120,
+        exclusive: true,
+        per_instance: true,
     },
     // uname -a > os/uname.txt
     Cmd {
+        id: "os.uname",
         command: "uname",
         args: "-a",
         file: "os/uname.txt",
@@ -387,10 +6565,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // sar -B > os/sar.txt
     Cmd {
+        id: "os.sar",
         command: "sar",
         args: "-B",
         file: "os/sar.txt",
@@ -398,10 +6579,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // lsblk > os/lsblk.txt
     Cmd {
+        id: "os.lsblk",
         command: "lsblk",
         args: "",
         file: "os/lsblk.txt",
@@ -409,10 +6593,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // lsblk -oname,kname,fstype,mountpoint,label,ra,model,size,rota  >  os/lsblk_custom.txt
     Cmd {
+        id: "os.lsblk_custom",
         command: "lsblk",
         args: "-oname,kname,fstype,mountpoint,label,ra,model,size,rota",
         file: "os/lsblk_custom.txt",
@@ -420,10 +6607,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // lspci > os/lspci.txt
     Cmd {
+        id: "os.lspci",
         command: "lspci",
         args: "",
         file: "os/lspci.txt",
@@ -431,10 +6621,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // hostname -f > os/hostname.txt
     Cmd {
+        id: "os.hostname",
         command: "hostname",
         args: "-f",
         file: "os/hostname.txt",
@@ -442,10 +6635,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  ps auxww > os/ps-aux.txt
     Cmd {
+        id: "os.ps-aux",
         command: "ps",
         args: "auxww",
         file: "os/ps-aux.txt",
@@ -453,10 +6649,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  uptime > os/uptime.txt
     Cmd {
+        id: "os.uptime",
         command: "uptime",
         args: "",
         file: "os/uptime.txt",
@@ -464,10 +6663,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  date > os/date.txt
     Cmd {
+        id: "os.date",
         command: "date",
         args: "",
         file: "os/date.txt",
@@ -475,10 +6677,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  ifconfig > os/ifconfig.txt
     Cmd {
+        id: "os.ifconfig",
         command: "ifconfig",
         args: "",
         file: "os/ifconfig.txt",
@@ -486,10 +6691,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  lscpu >  os/lscpu.txt
     Cmd {
+        id: "os.lscpu",
         command: "lscpu",
         args: "",
         file: "os/lscpu.txt",
@@ -497,10 +6705,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  ss -at > os/ss.txt
     Cmd {
+        id: "os.ss",
         command: "ss",
         args: "-at",
         file: "os/ss.txt",
@@ -508,10 +6719,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  top -n 10 -b -d 1 > os/top.txt
     Cmd {
+        id: "os.top",
         command: "top",
         args: "-n 10 -b -d 1",
         file: "os/top.txt",
@@ -519,10 +6733,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skipStat",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 60,
+        exclusive: false,
+        per_instance: false,
     },
     //  free
     Cmd {
+        id: "os.free",
         command: "free",
         args: "-m",
         file: "os/free.txt",
@@ -530,10 +6747,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //    vmstat 2 30 > "os/vmstat.txt
     Cmd {
+        id: "os.vmstat",
         command: "vmstat",
         args: "2 30",
         file: "os/vmstat.txt",
@@ -541,10 +6761,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skipStat",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 90,
+        exclusive: false,
+        per_instance: false,
     },
     //  env > os/env.txt
     Cmd {
+        id: "os.env",
         command: "env",
         args: "-u jmxPassword -u nodetoolCredentials -u cqlshPassword -u cqlsh_opts -u dt_opts -u SSHPASS",
         file: "os/env.txt",
@@ -552,10 +6775,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  java -version > os/java-version.txt 2>&1
     Cmd {
+        id: "os.java-version",
         command: "java",
         args: "-version",
         file: "os/java-version.txt",
@@ -563,10 +6789,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: false,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //    sudo -l > os/sudo-l.txt
     Cmd {
+        id: "os.sudo-l",
         command: "sudo",
         args: "-l",
         file: "os/sudo-l.txt",
@@ -574,10 +6803,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skipSudo",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  cat /sys/kernel/mm/transparent_hugepage/enabled > os/transparent_hugepage-enabled.txt
     Cmd {
+        id: "os.transparent_hugepage-enabled",
         command: "cat",
         args: "/sys/kernel/mm/transparent_hugepage/enabled",
         file: "os/transparent_hugepage-enabled.txt",
@@ -585,10 +6817,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  cat /sys/kernel/mm/transparent_hugepage/defrag > os/transparent_hugepage-defrag.txt
     Cmd {
+        id: "os.transparent_hugepage-defrag",
         command: "cat",
         args: "/sys/kernel/mm/transparent_hugepage/defrag",
         file: "os/transparent_hugepage-defrag.txt",
@@ -596,10 +6831,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     //  numactl --hardware > os/numactl-hardware.txt
     Cmd {
+        id: "os.numactl-hardware",
         command: "numactl",
         args: "--hardware",
         file: "os/numactl-hardware.txt",
@@ -607,10 +6845,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // cp -r /proc/cpuinfo /proc/meminfo /proc/interrupts /proc/version /etc/fstab /etc/security/limits.conf $artifactSubDir/
     Cmd {
+        id: "os.proc-files",
         command: "cp",
         args: "-r /proc/cpuinfo /proc/meminfo /proc/interrupts /proc/version /etc/fstab /etc/security/limits.conf {artifact_dir}/os/",
         file: "",
@@ -618,10 +6859,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // cp -r /etc/security/limits.d/* $artifactSubDir/limits.d/.
     Cmd {
+        id: "os.limits-d",
         command: "cp",
         args: "-r /etc/security/limits.d {artifact_dir}/os/",
         file: "",
@@ -629,11 +6873,14 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // cat /etc/*-release > $artifactSubDir/os.txt
     // use find as we can't use shell globs
     Cmd {
+        id: "os.os",
         command: "find",
         args: "/etc/ -name *-release -exec cat {} +",
         file: "os/os.txt",
@@ -641,10 +6888,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // curl http://169.254.169.254/latest/meta-data/instance-type > $artifactSubDir/instance_type.txt
     Cmd {
+        id: "cloud.instance_type",
         command: "curl",
         args: "--connect-timeout 10 -s http://169.254.169.254/latest/meta-data/instance-type",
         file: "cloud/instance_type.txt",
@@ -652,10 +6902,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // curl http://169.254.169.254/latest/meta-data/placement/availability-zone "$artifactSubDir/az_info.txt"
     Cmd {
+        id: "cloud.az_info",
         command: "curl",
         args: "--connect-timeout 10 -s http://169.254.169.254/latest/meta-data/placement/availability-zone",
         file: "cloud/az_info.txt",
@@ -663,10 +6916,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // "ec2metadata" > "$artifactDir/cloud/aws-metadata.txt
     Cmd {
+        id: "cloud.aws-metadata",
         command: "ec2metadata",
         args: "",
         file: "cloud/aws-metadata.txt",
@@ -674,10 +6930,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // curl --connect-timeout 10 http://metadata.google.internal/computeMetadata/v1/instance/ > $artifactDir/$sub_dir/google.txt
     Cmd {
+        id: "cloud.google",
         command: "curl",
         args: "--connect-timeout 10 -s http://metadata.google.internal/computeMetadata/v1/instance/",
         file: "cloud/google.txt",
@@ -685,10 +6944,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // slabtop -o -s c > $artifactDir/os/slaptop.txt
     Cmd {
+        id: "os.slaptop",
         command: "slabtop",
         args: "-o -s c",
         file: "os/slaptop.txt",
@@ -696,10 +6958,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // sysctl -a > $artifactDir/os/sysctl.txt
     Cmd {
+        id: "os.sysctl",
         command: "sysctl",
         args: "-a",
         file: "os/sysctl.txt",
@@ -707,10 +6972,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // blockdev --report > $artifactDir/os/blockdev-report.txt
     Cmd {
+        id: "os.blockdev-report",
         command: "blockdev",
         args: "--report",
         file: "os/blockdev-report.txt",
@@ -718,10 +6986,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // lsof -i -P | grep cassandra > $artifactDir/os/lsof-cassandra.txt
     Cmd {
+        id: "os.lsof-cassandra",
         command: "lsof",
         args: "-i -P", // TODO `| grep cassandra`
         file: "os/lsof-cassandra.txt",
@@ -729,10 +7000,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // netstat -nr > $artifactDir/os/netstat-nr.txt
     Cmd {
+        id: "os.netstat-nr",
         command: "netstat",
         args: "-nr",
         file: "os/netstat-nr.txt",
@@ -740,10 +7014,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // netstat -lptu > $artifactDir/os/netstat-lptu.txt
     Cmd {
+        id: "os.netstat-lptu",
         command: "netstat",
         args: "-lptu",
         file: "os/netstat-lptu.txt",
@@ -751,10 +7028,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // netstat -tulpn > $artifactDir/os/netstat-tulpn.txt
     Cmd {
+        id: "os.netstat-tulpn",
         command: "netstat",
         args: "-tulpn",
         file: "os/netstat-tulpn.txt",
@@ -762,10 +7042,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // cp -r $logHome/* $artifactSubDir/.
     Cmd {
+        id: "logs",
         command: "{base_dir}/etc/cpReadable.sh",
         args: "{log_home} {artifact_dir}/logs {logs_max_age_days}",
         file: "",
@@ -773,10 +7056,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 3600,
+        exclusive: false,
+        per_instance: true,
     },
     // cp -r $configHome/* $artifactSubDir/.
     Cmd {
+        id: "conf",
         command: "{base_dir}/etc/cpReadable.sh",
         args: "{config_home} {artifact_dir}/conf",
         file: "",
@@ -784,10 +7070,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 300,
+        exclusive: false,
+        per_instance: true,
     },
     // netstat --statistics > $artifactSubDir/netstat-summary.txt
     Cmd {
+        id: "network.netstat-summary",
         command: "netstat",
         args: "--statistics",
         file: "network/netstat-summary.txt",
@@ -795,10 +7084,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // ntpstat > $artifactSubDir/ntpstat.txt
     Cmd {
+        id: "network.ntpstat",
         command: "ntpstat",
         args: "",
         file: "network/ntpstat.txt",
@@ -806,10 +7098,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // ntpq -p > $artifactSubDir/ntpq-p.txt
     Cmd {
+        id: "network.ntpq-p",
         command: "ntpq",
         args: "-p",
         file: "network/ntpq-p.txt",
@@ -817,10 +7112,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // chronyc
     Cmd {
+        id: "network.chrony.tracking",
         command: "chronyc",
         args: "tracking",
         file: "network/chrony/tracking.txt",
@@ -828,9 +7126,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     Cmd {
+        id: "network.chrony.sources",
         command: "chronyc",
         args: "sources -v",
         file: "network/chrony/sources.txt",
@@ -838,9 +7139,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     Cmd {
+        id: "network.chrony.sourcestats",
         command: "chronyc",
         args: "sourcestats -v",
         file: "network/chrony/sourcestats.txt",
@@ -848,10 +7152,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // ifconfig > $artifactSubDir/ifconfig.txt
     Cmd {
+        id: "network.ifconfig",
         command: "ifconfig",
         args: "",
         file: "network/ifconfig.txt",
@@ -859,10 +7166,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // df -h > $artifactSubDir/df-size.txt
     Cmd {
+        id: "storage.df-size",
         command: "df",
         args: "-h",
         file: "storage/df-size.txt",
@@ -870,10 +7180,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // df -i > $artifactSubDir/df-inode.txt
     Cmd {
+        id: "storage.df-inode",
         command: "df",
         args: "-i",
         file: "storage/df-inode.txt",
@@ -881,10 +7194,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // iostat -dmx 5 24 > $artifactSubDir/iostat-dmx.txt
     Cmd {
+        id: "storage.iostat-dmx",
         command: "iostat",
         args: "-dmx 5 24 ",
         file: "storage/iostat-dmx.txt",
@@ -892,10 +7208,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skipStat",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 150,
+        exclusive: false,
+        per_instance: false,
     },
     // dstat -am  --output $artifactSubDir/dstat.txt 1 60
     Cmd {
+        id: "storage.dstat",
         command: "{base_dir}/dstat",
         args: "-am --output {artifact_dir}/storage/dstat.txt 1 60",
         file: "",
@@ -903,10 +7222,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skipStat",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 90,
+        exclusive: false,
+        per_instance: false,
     },
     // pvdisplay > $artifactSubDir/pvdisplay.txt
     Cmd {
+        id: "storage.pvdisplay",
         command: "pvdisplay",
         args: "",
         file: "storage/pvdisplay.txt",
@@ -914,10 +7236,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // vgdisplay > $artifactSubDir/vgdisplay.txt
     Cmd {
+        id: "storage.vgdisplay",
         command: "vgdisplay",
         args: "",
         file: "storage/vgdisplay.txt",
@@ -925,10 +7250,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // lvdisplay -a > $artifactSubDir/lvdisplay.txt
     Cmd {
+        id: "storage.lvdisplay",
         command: "lvdisplay",
         args: "-a",
         file: "storage/lvdisplay.txt",
@@ -936,10 +7264,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // lvs -a > $artifactSubDir/lvs.txt
     Cmd {
+        id: "storage.lvs",
         command: "lvs",
         args: "-a",
         file: "storage/lvs.txt",
@@ -947,10 +7278,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: false,
     },
     // jcmd $cassandra_pid VM.system_properties > java_system_properties.txt
     Cmd {
+        id: "java_system_properties",
         command: "jcmd",
         args: "{cassandra_pid} VM.system_properties",
         file: "java_system_properties.txt",
@@ -958,10 +7292,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     // jcmd $cassandra_pid VM.command_line > java_command_line.txt
     Cmd {
+        id: "java_command_line",
         command: "jcmd",
         args: "{cassandra_pid} VM.command_line",
         file: "java_command_line.txt",
@@ -969,10 +7306,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     // nodetool $jmxHost -p $jmxPort $nodetoolCredentials $nodetoolCmd > "$artifactSubDir/$nodetoolCmd.txt"
     Cmd {
+        id: "nodetool.status",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} status",
         file: "nodetool/status.txt",
@@ -980,9 +7320,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.tpstats",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} tpstats",
         file: "nodetool/tpstats.txt",
@@ -990,9 +7333,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
+    // the per-table commands walk every table over JMX, on large schemas they need far longer than the default timeout
     Cmd {
+        id: "nodetool.cfhistograms",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} cfhistograms",
         file: "nodetool/tablehistograms.txt",
@@ -1000,10 +7347,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 300,
+        exclusive: true,
+        per_instance: true,
     },
     // Cassandra 5.0 renamed cfhistograms to tablehistograms, so just try collect both (with optional: true)
     Cmd {
+        id: "nodetool.tablehistograms",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} tablehistograms",
         file: "nodetool/tablehistograms.txt",
@@ -1011,9 +7361,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 300,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.cfstats",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} cfstats",
         file: "nodetool/cfstats.txt",
@@ -1021,10 +7374,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 300,
+        exclusive: true,
+        per_instance: true,
     },
     // Cassandra 5.0 renamed cfstats to tablestats, so just try collect both (with optional: true)
     Cmd {
+        id: "nodetool.tablestats",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} tablestats",
         file: "nodetool/cfstats.txt",
@@ -1032,9 +7388,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 300,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.info",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} info",
         file: "nodetool/info.txt",
@@ -1042,9 +7401,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.ring",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} ring",
         file: "nodetool/ring.txt",
@@ -1052,9 +7414,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 120,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.version",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} version",
         file: "nodetool/version.txt",
@@ -1062,9 +7427,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.proxyhistograms",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} proxyhistograms",
         file: "nodetool/proxyhistograms.txt",
@@ -1072,9 +7440,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 120,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.compactionstats",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} compactionstats",
         file: "nodetool/compactionstats.txt",
@@ -1082,9 +7453,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 120,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.compactionhistory",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} compactionhistory",
         file: "nodetool/compactionhistory.txt",
@@ -1092,9 +7466,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 120,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.describecluster",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} describecluster",
         file: "nodetool/describecluster.txt",
@@ -1102,9 +7479,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 120,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.getcompactionthroughput",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} getcompactionthroughput",
         file: "nodetool/getcompactionthroughput.txt",
@@ -1112,9 +7492,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.getstreamthroughput",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} getstreamthroughput",
         file: "nodetool/getstreamthroughput.txt",
@@ -1122,9 +7505,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.gossipinfo",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} gossipinfo",
         file: "nodetool/gossipinfo.txt",
@@ -1132,9 +7518,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 120,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.netstats",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} netstats",
         file: "nodetool/netstats.txt",
@@ -1142,9 +7531,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 120,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.statusbinary",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} statusbinary",
         file: "nodetool/statusbinary.txt",
@@ -1152,9 +7544,12 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     Cmd {
+        id: "nodetool.statusthrift",
         command: "nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} statusthrift",
         file: "nodetool/statusthrift.txt",
@@ -1162,10 +7557,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     // collect all the sstable -Statistics.db' files
     Cmd {
+        id: "sstable-statistics",
         command: "find",
         args: "{data_dir} -maxdepth 3 -name *-Statistics.db -exec cp --parents {} {artifact_dir}/sstable-statistics/ ;",
         file: "",
@@ -1173,13 +7571,16 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_db_statistics",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 600,
+        exclusive: false,
+        per_instance: true,
     },
 
     // DSE //
 
     // cp "$dse_conf_dir/dse.yaml" "$artifactDir/conf/dse/"
     Cmd {
+        id: "conf.dse.yaml",
         command: "cat",
         args: "{dse_conf_dir}/dse.yaml",
         file: "conf/dse/dse.yaml",
@@ -1187,10 +7588,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_dse",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: true,
     },
     // cp "/etc/default/dse" "$artifactDir/conf/dse/"
     Cmd {
+        id: "conf.dse.default",
         command: "{base_dir}/etc/cpReadable.sh",
         args: "/etc/default/dse {artifact_dir}/conf/dse/",
         file: "",
@@ -1198,10 +7602,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_dse",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: true,
     },
     // cp "$logHome/audit/dropped-events.log" "$artifactDir/logs/cassandra/audit"
     Cmd {
+        id: "logs.cassandra.audit.dropped-events",
         command: "cat",
         args: "{log_home}/audit/dropped-events.log {artifact_dir}/",
         file: "logs/cassandra/audit/dropped-events.log",
@@ -1209,10 +7616,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_dse",
         use_stdout: true,
         use_sudo: true,
-        use_timeout: false,
+        timeout: 0,
+        exclusive: false,
+        per_instance: true,
     },
     // $dse_bin_dir/dsetool $dt_opts status > "$artifactDir/dsetool/status"
     Cmd {
+        id: "dsetool.status",
         command: "{dse_bin_dir}dsetool",
         args: "{dt_opts} status",
         file: "dsetool/status.txt",
@@ -1220,10 +7630,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_dse",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     // $dse_bin_dir/dsetool $dt_opts ring > "$artifactDir/dsetool/ring"
     Cmd {
+        id: "dsetool.ring",
         command: "{dse_bin_dir}dsetool",
         args: "{dt_opts} ring",
         file: "dsetool/ring.txt",
@@ -1231,10 +7644,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_dse",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     // $dse_bin_dir/dsetool $dt_opts insights_config --show_config > "$artifactDir/dsetool/insights_config"
     Cmd {
+        id: "dsetool.insights_config",
         command: "{dse_bin_dir}dsetool",
         args: "{dt_opts} insights_config --show_config",
         file: "dsetool/insights_config.txt",
@@ -1242,10 +7658,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_dse",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     // $dse_bin_dir/dsetool $dt_opts insights_filters --show_filters > "$artifactDir/dsetool/insights_filters"
     Cmd {
+        id: "dsetool.insights_filters",
         command: "{dse_bin_dir}dsetool",
         args: "{dt_opts} insights_filters --show_filters",
         file: "dsetool/insights_filters.txt",
@@ -1253,10 +7672,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_dse",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     // $dse_bin_dir/dsetool $dt_opts perf cqlslowlog recent_slowest_queries > "$artifactDir/dsetool/slowest_queries"
     Cmd {
+        id: "dsetool.slowest_queries",
         command: "{dse_bin_dir}dsetool",
         args: "{dt_opts} perf cqlslowlog recent_slowest_queries",
         file: "dsetool/slowest_queries.txt",
@@ -1264,10 +7686,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_dse",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     // $dse_bin_dir/nodetool $jmxHost -p $jmxPort $nodetoolCredentials nodesyncservice getrate > "$artifactDir/nodetool/nodesyncrate"
     Cmd {
+        id: "nodetool.nodesyncrate",
         command: "{dse_bin_dir}nodetool",
         args: "-h {jmx_host} -p {jmx_port} {nodetool_credentials} {nodetool_ssl} nodesyncservice getrate",
         file: "nodetool/nodesyncrate.txt",
@@ -1275,10 +7700,13 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_dse no_nodesyncrate",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: true,
+        per_instance: true,
     },
     // cd "$solr_data_dir" && du -s -- *
     Cmd {
+        id: "solr.cores-sizes",
         command: "du",
         args: "-s -- {solr_data_dir}/",
         file: "solr/cores-sizes.txt",
@@ -1286,6 +7714,739 @@ const COMMANDS: &[Cmd<'static>] = &[
         skip_flags: "skip_dse skip_dse_solr",
         use_stdout: true,
         use_sudo: false,
-        use_timeout: true,
+        timeout: 0,
+        exclusive: false,
+        per_instance: true,
     },
 ];
+
+// rustc --test collect-info.rs -o collect-info-tests && ./collect-info-tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lane_of(lanes: &[Vec<usize>], id: &str) -> usize {
+        let i = COMMANDS.iter().position(|cmd| id == cmd.id).unwrap();
+        lanes.iter().position(|lane| lane.contains(&i)).unwrap()
+    }
+
+    #[test]
+    fn lanes_serialise_commands_writing_under_a_replaced_directory() {
+        let lanes = create_lanes(COMMANDS, 8);
+        assert_eq!(lane_of(&lanes, "logs"), lane_of(&lanes, "logs.cassandra.audit.dropped-events"));
+        assert_eq!(lane_of(&lanes, "conf"), lane_of(&lanes, "conf.dse.default"));
+        assert_ne!(lane_of(&lanes, "logs"), lane_of(&lanes, "conf"));
+        // every command is in exactly one lane, in its original order
+        let mut all: Vec<usize> = lanes.iter().flatten().cloned().collect();
+        assert!(lanes.iter().all(|lane| lane.windows(2).all(|pair| pair[0] < pair[1])));
+        all.sort();
+        assert_eq!((0..COMMANDS.len()).collect::<Vec<usize>>(), all);
+    }
+
+    #[test]
+    fn dsetool_credentials_move_out_of_the_arguments() {
+        let (opts, credentials) = split_dsetool_credentials("-h 127.0.0.1 -p 7199 -u cassandra -pw 'se cret' --ssl");
+        assert_eq!(vec!["-h", "127.0.0.1", "-p", "7199", "--ssl"], opts);
+        assert_eq!(Some(("cassandra".to_string(), "se cret".to_string())), credentials);
+        assert_eq!(None, split_dsetool_credentials("-h 127.0.0.1 -u cassandra").1);
+    }
+
+    #[test]
+    fn jjs_script_holds_the_secret_arguments() {
+        let script = format!("{}/collect-info-test-{}.js", env::temp_dir().display(), std::process::id());
+        let arg_list: Vec<String> = ["-Djavax.net.ssl.keyStorePassword=ks\"pw", "-Xmx64m", "-cp", "/opt/jmx.jar", "io.prometheus.jmx.JmxScraper", "service:jmx:rmi:///jndi/rmi://127.0.0.1:7199/jmxrmi", "cassandra", "secret"]
+            .iter().map(|arg| arg.to_string()).collect();
+        let jjs_args = write_jjs_script(&script, &arg_list).unwrap();
+        let content = std::fs::read_to_string(&script).unwrap();
+        std::fs::remove_file(&script).ok();
+        assert_eq!(vec!["-J-Xmx64m".to_string(), "-cp".to_string(), "/opt/jmx.jar".to_string(), script.clone()], jjs_args);
+        assert!(!jjs_args.iter().any(|arg| arg.contains("secret") || arg.contains("ks")));
+        assert_eq!(
+            "java.lang.System.setProperty(\"javax.net.ssl.keyStorePassword\", \"ks\\\"pw\");\n\
+             Java.type(\"io.prometheus.jmx.JmxScraper\").main(Java.to([\"service:jmx:rmi:///jndi/rmi://127.0.0.1:7199/jmxrmi\", \"cassandra\", \"secret\"], \"java.lang.String[]\"));\n",
+            content
+        );
+    }
+
+    #[test]
+    fn only_logs_are_exempt_from_redaction_by_size() {
+        assert!(is_log_file("logs/system.log"));
+        assert!(is_log_file("instances/node2/logs/gc.log.0"));
+        assert!(is_log_file("logs-window/debug.log"));
+        assert!(!is_log_file("conf/cassandra.yaml"));
+        assert!(!is_log_file("logs"));
+        assert!(!is_log_file("nodetool/logs.txt"));
+    }
+
+    #[test]
+    fn redaction_keeps_the_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = test_dir("redact");
+        let yaml = dir.join("cassandra.yaml");
+        std::fs::write(&yaml, "cluster_name: test\nkeystore_password: s3cret\n").unwrap();
+        std::fs::set_permissions(&yaml, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let rules = RedactionRules { include: vec!["*password*".to_string()], exclude: Vec::new(), literals: Vec::new() };
+        assert_eq!(vec![("keystore_password".to_string(), 1)], redact_file(&yaml, &rules).unwrap());
+        let content = std::fs::read_to_string(&yaml).unwrap();
+        assert!(content.starts_with("cluster_name: test\nkeystore_password: ") && !content.contains("s3cret"), "{}", content);
+        assert_eq!(0o600, std::fs::metadata(&yaml).unwrap().permissions().mode() & 0o777);
+        assert!(!dir.join("cassandra.yaml.redacting").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn cassandra_yaml_data_directories_in_either_sequence_style() {
+        let block = "cluster_name: 'Test Cluster'\ndata_file_directories:\n    - /data/a\n    - \"/data/b\" # second disk\ncommitlog_directory: /commitlog\n";
+        let (configured, data_dirs) = parse_cassandra_yaml(block);
+        assert_eq!(vec!["/data/a", "/data/b"], data_dirs);
+        assert_eq!(Some(&"/commitlog".to_string()), configured.get("commitlog_directory"));
+        assert_eq!(Some(&"Test Cluster".to_string()), configured.get("cluster_name"));
+
+        let flow = "data_file_directories: [/data/a, '/data/b'] # two disks\nhints_directory: /hints\n";
+        let (configured, data_dirs) = parse_cassandra_yaml(flow);
+        assert_eq!(vec!["/data/a", "/data/b"], data_dirs);
+        assert_eq!(Some(&"/hints".to_string()), configured.get("hints_directory"));
+        assert_eq!(None, configured.get("data_file_directories"));
+    }
+
+    #[test]
+    fn mounts_are_read_from_the_given_process() {
+        let own = read_mountinfo(&std::process::id().to_string()).unwrap();
+        let current = read_mountinfo("").unwrap();
+        assert!(!own.is_empty());
+        assert_eq!(current.iter().map(|mount| &mount.mount_point).collect::<Vec<_>>(), own.iter().map(|mount| &mount.mount_point).collect::<Vec<_>>());
+        assert!(read_mountinfo("0").is_err());
+    }
+
+    #[test]
+    fn log_lines_of_each_version_parse() {
+        // Cassandra 3.11
+        let line = parse_log_line("INFO  [main] 2024-03-01 14:05:09,123 StorageService.java:1544 - JOINING: Starting to bootstrap...").unwrap();
+        assert_eq!(("INFO", "main", "2024-03-01 14:05:09,123", "StorageService"), (line.level, line.thread, line.timestamp, line.logger));
+        assert_eq!("JOINING: Starting to bootstrap...", line.message);
+        // Cassandra 4.x, the message holding " - " too
+        let line = parse_log_line("WARN  [ScheduledTasks:1] 2024-03-01T14:05:09.123 NoSpamLogger.java:95 - Some operations timed out - see the logs").unwrap();
+        assert_eq!(("WARN", "ScheduledTasks:1", "2024-03-01T14:05:09.123", "NoSpamLogger"), (line.level, line.thread, line.timestamp, line.logger));
+        assert_eq!("Some operations timed out - see the logs", line.message);
+        // DSE, with a second space before the source and "] " in the thread name
+        let line = parse_log_line("ERROR [CoreThread-3] [ks] 2024-03-01 14:05:09,123  DseDaemon.java:810 - Unable to start").unwrap();
+        assert_eq!(("ERROR", "CoreThread-3] [ks", "DseDaemon", "Unable to start"), (line.level, line.thread, line.logger, line.message));
+
+        assert!(parse_log_line("java.lang.RuntimeException: boom").is_none());
+        assert!(parse_log_line("\tat org.apache.cassandra.db.Keyspace.apply(Keyspace.java:100)").is_none());
+        assert!(parse_log_line("NOTICE [main] 2024-03-01 14:05:09,123 Foo.java:1 - bar").is_none());
+        assert!(parse_log_line("INFO  [main] 2024-03-01 14:05 Foo.java:1 - short timestamp").is_none());
+    }
+
+    #[test]
+    fn log_analysis_skips_stack_traces_and_counts_repeated_lines_once() {
+        let system = "INFO  [main] 2024-03-01 14:05:09,123 CassandraDaemon.java:500 - Startup complete\n\
+            ERROR [ReadStage-2] 2024-03-01 14:06:00,000 JVMStabilityInspector.java:68 - Exception in thread Thread[ReadStage-2,5,main]\n\
+            java.lang.RuntimeException: org.apache.cassandra.db.filter.TombstoneOverwhelmingException\n\
+            \tat org.apache.cassandra.service.StorageProxy$DroppableRunnable.run(StorageProxy.java:2605)\n\
+            \tat java.lang.Thread.run(Thread.java:748)\n\
+            INFO  [Service Thread] 2024-03-01 14:07:00,000 GCInspector.java:284 - G1 Young Generation GC in 250ms.  G1 Eden Space: 1 -> 0\n\
+            INFO  [ScheduledTasks:1] 2024-03-01 14:08:00,000 MessagingService.java:1236 - MUTATION messages were dropped in last 5000 ms: 3 internal and 12 cross node. Mean internal dropped latency: 1 ms\n\
+            INFO  [GossipStage:1] 2024-03-01 14:09:00,000 Gossiper.java:1100 - InetAddress /10.0.0.2:7000 is now DOWN\n";
+        let debug = format!("DEBUG [main] 2024-03-01 14:05:00,000 Foo.java:1 - debug only\n{}", system);
+        let mut findings = LogFindings::default();
+        assert_eq!(system.len() as u64, analyse_log("system.log", &mut system.as_bytes(), &mut findings).unwrap());
+        analyse_log("debug.log", &mut debug.as_bytes(), &mut findings).unwrap();
+
+        assert_eq!(("system.log".to_string(), 5, "2024-03-01 14:05:09,123".to_string(), "2024-03-01 14:09:00,000".to_string()), findings.files[0]);
+        assert_eq!(("debug.log".to_string(), 6, "2024-03-01 14:05:00,000".to_string(), "2024-03-01 14:09:00,000".to_string()), findings.files[1]);
+        assert_eq!(("2024-03-01 14:05:00,000", "2024-03-01 14:09:00,000"), (findings.first.as_str(), findings.last.as_str()));
+        assert_eq!(Some(&1), findings.warn_error_by_logger.get(&("ERROR".to_string(), "JVMStabilityInspector".to_string())));
+        assert_eq!(1, findings.warn_error_by_logger.len());
+        let young = &findings.gc_pauses["G1 Young Generation"];
+        assert_eq!((1, 250, "2024-03-01 14:07:00,000"), (young.count, young.max_ms, young.max_at.as_str()));
+        assert_eq!(Some(&(1, 15)), findings.dropped_messages.get("MUTATION"));
+        assert_eq!(&(1, 0, "DOWN".to_string(), "2024-03-01 14:09:00,000".to_string()), &findings.gossip["10.0.0.2:7000"]);
+    }
+
+    #[test]
+    fn log_tail_starts_at_a_whole_line() {
+        let dir = test_dir("log-tail");
+        let lines: String = (0..100).map(|i| format!("INFO  [main] 2024-03-01 14:05:{:02},000 Foo.java:1 - line {:02}\n", i % 60, i)).collect();
+        std::fs::write(dir.join("system.log"), &lines).unwrap();
+        let mut findings = LogFindings::default();
+        // a little over the last ten lines
+        let line_length = lines.len() as u64 / 100;
+        analyse_log_tail(&dir.join("system.log"), line_length * 10 + 5, &mut findings).unwrap();
+        assert_eq!(10, findings.files[0].1);
+        assert_eq!("2024-03-01 14:05:39,000", findings.files[0].3);
+        let mut findings = LogFindings::default();
+        analyse_log_tail(&dir.join("system.log"), u64::MAX, &mut findings).unwrap();
+        assert_eq!(100, findings.files[0].1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    // (kind, milliseconds, at, hour) of each pause
+    fn gc_pauses(log: &str) -> (Vec<(&'static str, f64, String, String)>, GcFindings) {
+        let mut findings = GcFindings::default();
+        analyse_gc_log("gc.log", &mut log.as_bytes(), &mut findings).unwrap();
+        let pauses = findings.pauses.iter().map(|pause| (pause.kind, pause.milliseconds, pause.at.clone(), pause.hour.clone())).collect();
+        (pauses, findings)
+    }
+
+    #[test]
+    fn gc_legacy_log() {
+        let log = "Java HotSpot(TM) 64-Bit Server VM (25.292-b10), built on Apr  5 2021\n\
+            2024-03-01T14:05:09.123+0000: 12.345: [GC (Allocation Failure) 2024-03-01T14:05:09.123+0000: 12.345: [ParNew: 100K->10K(200K), 0.0123456 secs] 300K->210K(1000K), 0.0125000 secs] [Times: user=0.05 sys=0.00, real=0.01 secs]\n\
+            2024-03-01T14:06:00.000+0000: 60.000: [GC (Allocation Failure) 2024-03-01T14:06:00.000+0000: 60.000: [ParNew\n\
+            Desired survivor size 1000 bytes, new threshold 1 (max 1)\n\
+            - age   1:        100 bytes,        100 total\n\
+            : 100K->10K(200K), 0.0200000 secs] 300K->210K(1000K), 0.0210000 secs] [Times: user=0.05 sys=0.00, real=0.02 secs]\n\
+            2024-03-01T15:00:00.000+0000: 3300.000: [GC (CMS Initial Mark) [1 CMS-initial-mark: 500K(800K)] 510K(1000K), 0.0050000 secs] [Times: user=0.01 sys=0.00, real=0.01 secs]\n\
+            2024-03-01T15:00:00.005+0000: 3300.005: [CMS-concurrent-mark-start]\n\
+            2024-03-01T15:09:59.000+0000: 3899.000: [CMS-concurrent-mark: 0.100/0.200 secs] (concurrent mode failure)\n\
+            2024-03-01T15:10:00.000+0000: 3900.000: [GC (Allocation Failure) 2024-03-01T15:10:00.000+0000: 3900.000: [ParNew: 100K->100K(200K), 0.0000100 secs]2024-03-01T15:10:00.000+0000: 3900.000: [CMS: 500K->400K(800K), 1.5000000 secs] 600K->400K(1000K), [Metaspace: 1K->1K(2K)], 1.5100000 secs] [Times: user=1.50 sys=0.00, real=1.51 secs]\n\
+            7200.500: [GC pause (G1 Humongous Allocation) (young) (to-space exhausted), 0.0300000 secs]\n\
+            [GC pause (G1 Evacuation Pause) (mixed), 0.0400000 secs]\n";
+        let (pauses, findings) = gc_pauses(log);
+        let hour = |hour: &str| hour.to_string();
+        assert_eq!(
+            vec![
+                ("Young", 12.5, "2024-03-01T14:05:09.123+0000".to_string(), hour("2024-03-01 14:00")),
+                ("Young", 21.0, "2024-03-01T14:06:00.000+0000".to_string(), hour("2024-03-01 14:00")),
+                ("Initial Mark", 5.0, "2024-03-01T15:00:00.000+0000".to_string(), hour("2024-03-01 15:00")),
+                ("Full", 1510.0, "2024-03-01T15:10:00.000+0000".to_string(), hour("2024-03-01 15:00")),
+                ("Young", 30.0, "uptime 7200.500s".to_string(), hour("uptime hour     2")),
+                ("Mixed", 40.0, "unknown".to_string(), hour("unknown")),
+            ],
+            pauses
+        );
+        assert_eq!(vec!["CMS", "G1"], findings.collectors);
+        assert_eq!((1, 1, 1), (findings.concurrent_mode_failures, findings.humongous_allocations, findings.evacuation_failures));
+        assert_eq!(vec![("gc.log".to_string(), 6)], findings.files);
+    }
+
+    #[test]
+    fn gc_unified_log() {
+        let log = "[2024-03-01T14:00:00.000+0000][0.010s][info][gc,init] Using G1\n\
+            [2024-03-01T14:05:09.123+0000][12.345s][info][gc,start    ] GC(12) Pause Young (Normal) (G1 Evacuation Pause)\n\
+            [2024-03-01T14:05:09.135+0000][12.357s][info][gc          ] GC(12) Pause Young (Normal) (G1 Evacuation Pause) 100M->50M(1024M) 12.345ms\n\
+            [2024-03-01T14:06:00.000+0000][60.000s][info][gc          ] GC(13) To-space exhausted\n\
+            [2024-03-01T14:06:00.100+0000][60.100s][info][gc          ] GC(13) Pause Young (Normal) (G1 Humongous Allocation) 900M->1000M(1024M) 100.000ms\n\
+            [2024-03-01T14:07:00.000+0000][120.000s][info][gc          ] GC(14) Pause Remark 500M->500M(1024M) 5.000ms\n\
+            [2024-03-01T15:08:00.000+0000][3780.000s][info][gc          ] GC(15) Pause Full (G1 Evacuation Pause) 1000M->400M(1024M) 1500.000ms\n\
+            [90000ms][info][gc] GC(16) Pause Young (Concurrent Start) (G1 Evacuation Pause) 10M->5M(100M) 2.500ms\n";
+        let (pauses, findings) = gc_pauses(log);
+        let hour = |hour: &str| hour.to_string();
+        assert_eq!(
+            vec![
+                ("Young", 12.345, "2024-03-01T14:05:09.135+0000".to_string(), hour("2024-03-01 14:00")),
+                ("Young", 100.0, "2024-03-01T14:06:00.100+0000".to_string(), hour("2024-03-01 14:00")),
+                ("Remark", 5.0, "2024-03-01T14:07:00.000+0000".to_string(), hour("2024-03-01 14:00")),
+                ("Full", 1500.0, "2024-03-01T15:08:00.000+0000".to_string(), hour("2024-03-01 15:00")),
+                ("Young", 2.5, "uptime 90.000s".to_string(), hour("uptime hour     0")),
+            ],
+            pauses
+        );
+        assert_eq!((1, 1), (findings.humongous_allocations, findings.evacuation_failures));
+
+        let zgc = "[2024-03-01T16:00:00.000+0000][0.010s][info][gc,init] Initializing The Z Garbage Collector\n\
+            [2024-03-01T16:00:01.000+0000][1.000s][info][gc,phases   ] GC(3) Pause Mark Start 0.010ms\n\
+            [2024-03-01T16:00:01.100+0000][1.100s][info][gc,phases   ] GC(3) Pause Mark End 0.020ms\n\
+            [2024-03-01T16:00:01.200+0000][1.200s][info][gc,phases   ] GC(3) Pause Relocate Start 0.030ms\n\
+            [2024-03-01T16:00:02.000+0000][2.000s][info][gc          ] Allocation Stall (Thread-1) 12.345ms\n";
+        let (pauses, findings) = gc_pauses(zgc);
+        assert_eq!(vec!["Mark Start", "Mark End", "Relocate Start"], pauses.iter().map(|pause| pause.0).collect::<Vec<&str>>());
+        assert_eq!((vec!["ZGC"], 1), (findings.collectors.clone(), findings.allocation_stalls));
+    }
+
+    #[test]
+    fn gc_wall_clock_not_ascii_or_too_short() {
+        // the hour's first digit followed by a two byte character, once a panic slicing within it
+        let (pauses, _) = gc_pauses("[2024-03-01T1\u{e9}:00:00.000+0000][7.000s][info][gc] GC(1) Pause Young (Normal) (G1 Evacuation Pause) 10M->5M(100M) 1.000ms\n");
+        assert_eq!(vec![("Young", 1.0, "uptime 7.000s".to_string(), "uptime hour     0".to_string())], pauses);
+        let (pauses, _) = gc_pauses("2024-03-01T1\u{e9}:00:00.000+0000: 7.000: [GC pause (G1 Evacuation Pause) (young), 0.0010000 secs]\n");
+        assert_eq!(vec![("Young", 1.0, "uptime 7.000s".to_string(), "uptime hour     0".to_string())], pauses);
+        assert_eq!(("unknown".to_string(), "unknown".to_string()), gc_time(Some("2024-03-01"), None));
+        assert_eq!(("uptime 1.000s".to_string(), "uptime hour     0".to_string()), gc_time(Some("2024-03-01T1"), Some(1.0)));
+    }
+
+    // nodetool tpstats, as printed by each version
+    const TPSTATS_3_11: &str = "Pool Name                         Active   Pending      Completed   Blocked  All time blocked\n\
+        ReadStage                              0         0          39312         0                 0\n\
+        MutationStage                          2         5        1046315         0                 0\n\
+        Native-Transport-Requests              1         0        2241932         0                12\n\
+        \n\
+        Message type           Dropped\n\
+        READ                         0\n\
+        MUTATION                    41\n";
+    const TPSTATS_4_0: &str = "Pool Name                    Active Pending Completed Blocked All time blocked\n\
+        ReadStage                    0      0       39312     0       0\n\
+        MutationStage                2      5       1046315   0       0\n\
+        Native-Transport-Requests    1      0       2241932   0       12\n\
+        \n\
+        Latencies waiting in queue (micros) per dropped message types\n\
+        Message type           Dropped                  Latency waiting in queue (micros)\n\
+        \x20                                            50%               95%               99%               Max\n\
+        READ_RSP                     0                  0.0               0.0               0.0               0.0\n\
+        MUTATION_REQ                41               1131.752          3379.391          4866.323          8409.007\n";
+    const TPSTATS_5_0: &str = "Pool Name                         Active Pending Completed Blocked All time blocked\n\
+        ReadStage                         0      0       39312     0       0\n\
+        MutationStage                     2      5       1046315   0       0\n\
+        Native-Transport-Auth-Requests    0      0       3         0       0\n\
+        Native-Transport-Requests         1      0       2241932   0       12\n\
+        \n\
+        Message type           Dropped                  Latency waiting in queue (micros)\n\
+        \x20                                            50%               95%               99%               Max\n\
+        MUTATION_REQ                41               1131.752          3379.391          4866.323          8409.007\n";
+
+    #[test]
+    fn tpstats_of_each_version() {
+        let pool = "{\"name\":\"MutationStage\",\"active\":2,\"pending\":5,\"completed\":1046315,\"blocked\":0,\"all_time_blocked\":0}";
+        let requests = "{\"name\":\"Native-Transport-Requests\",\"active\":1,\"pending\":0,\"completed\":2241932,\"blocked\":0,\"all_time_blocked\":12}";
+        let latency = "\"latency_waiting_in_queue_micros\":{\"p50\":1131.752,\"p95\":3379.391,\"p99\":4866.323,\"max\":8409.007}";
+        for (version, tpstats, pools, dropped) in [
+            ("3.11", TPSTATS_3_11, 3, "{\"type\":\"MUTATION\",\"dropped\":41}".to_string()),
+            ("4.0", TPSTATS_4_0, 3, format!("{{\"type\":\"MUTATION_REQ\",\"dropped\":41,{}}}", latency)),
+            ("5.0", TPSTATS_5_0, 4, format!("{{\"type\":\"MUTATION_REQ\",\"dropped\":41,{}}}", latency)),
+        ].iter() {
+            let json = parse_nodetool_tpstats(tpstats).unwrap();
+            assert!(json.contains(pool) && json.contains(requests), "{}: {}", version, json);
+            assert!(json.contains(dropped.as_str()), "{}: {}", version, json);
+            assert_eq!(*pools, json.matches("\"all_time_blocked\"").count(), "{}: {}", version, json);
+        }
+        assert_eq!(Err("no thread pools found".to_string()), parse_nodetool_tpstats("Message type           Dropped\nREAD                         0\n"));
+    }
+
+    #[test]
+    fn status_of_each_version() {
+        // 3.11, then 4.0 (and 5.0, which prints it the same)
+        let status_3_11 = "Datacenter: dc1\n\
+            ===============\n\
+            Status=Up/Down\n\
+            |/ State=Normal/Leaving/Joining/Moving\n\
+            --  Address    Load       Tokens       Owns (effective)  Host ID                               Rack\n\
+            UN  10.0.0.1   1.23 GiB   256          66.7%             aaaaaaaa-1111-2222-3333-444444444444  rack1\n\
+            DN  10.0.0.2   ?          256          66.7%             bbbbbbbb-1111-2222-3333-444444444444  rack1\n";
+        let status_4_0 = "Datacenter: dc1\n\
+            ===============\n\
+            Status=Up/Down\n\
+            |/ State=Normal/Leaving/Joining/Moving\n\
+            --  Address    Load        Tokens  Owns (effective)  Host ID                               Rack \n\
+            UN  10.0.0.1   1.23 GiB    16      66.7%             aaaaaaaa-1111-2222-3333-444444444444  rack1\n\
+            UJ  10.0.0.3   104.82 KiB  16      ?                 cccccccc-1111-2222-3333-444444444444  rack2\n";
+        let status_5_0 = status_4_0.replace("dc1", "datacenter1");
+        let json = parse_nodetool_status(status_3_11).unwrap();
+        assert!(json.contains("{\"datacenter\":\"dc1\",\"address\":\"10.0.0.1\",\"status\":\"Up\",\"state\":\"Normal\",\"load_bytes\":1320702444,\"tokens\":256,\"owns_percent\":66.7,\"host_id\":\"aaaaaaaa-1111-2222-3333-444444444444\",\"rack\":\"rack1\"}"), "{}", json);
+        assert!(json.contains("\"address\":\"10.0.0.2\",\"status\":\"Down\",\"state\":\"Normal\",\"load_bytes\":null,\"tokens\":256"), "{}", json);
+        for status in [status_4_0, &status_5_0].iter() {
+            let json = parse_nodetool_status(status).unwrap();
+            assert!(json.contains("\"address\":\"10.0.0.1\",\"status\":\"Up\",\"state\":\"Normal\",\"load_bytes\":1320702444,\"tokens\":16,\"owns_percent\":66.7"), "{}", json);
+            assert!(json.contains("\"address\":\"10.0.0.3\",\"status\":\"Up\",\"state\":\"Joining\",\"load_bytes\":107336,\"tokens\":16,\"owns_percent\":null,\"host_id\":\"cccccccc-1111-2222-3333-444444444444\",\"rack\":\"rack2\"}"), "{}", json);
+        }
+        assert_eq!(Err("no nodes found".to_string()), parse_nodetool_status("Datacenter: dc1\n"));
+    }
+
+    #[test]
+    fn header_columns_by_spacing_or_by_name() {
+        let status = "--  Address    Load        Tokens  Owns (effective)  Host ID                               Rack ";
+        assert_eq!(vec!["--", "Address", "Load", "Tokens", "Owns (effective)", "Host ID", "Rack"], split_columns(status));
+        let tpstats_3_11 = "Pool Name                         Active   Pending      Completed   Blocked  All time blocked";
+        assert_eq!(vec!["Pool Name", "Active", "Pending", "Completed", "Blocked", "All time blocked"], split_columns(tpstats_3_11));
+        // single spaced since 4.0, where split_columns can't tell the columns apart
+        let tpstats_4_0 = "Pool Name                    Active Pending Completed Blocked All time blocked";
+        assert_eq!(vec!["Pool Name", "Active Pending Completed Blocked All time blocked"], split_columns(tpstats_4_0));
+        for header in [tpstats_3_11, tpstats_4_0].iter() {
+            let words: Vec<&str> = header.split_whitespace().collect();
+            assert_eq!(vec!["Pool Name", "Active", "Pending", "Completed", "Blocked", "All time blocked"], header_columns(&words, TPSTATS_COLUMNS));
+        }
+        let words: Vec<&str> = "Percentile  Read Latency     Write Latency     Range Latency   CAS Read Latency   CAS Write Latency View Write Latency".split_whitespace().collect();
+        assert_eq!(
+            vec!["Percentile", "Read Latency", "Write Latency", "Range Latency", "CAS Read Latency", "CAS Write Latency", "View Write Latency"],
+            header_columns(&words, HISTOGRAM_COLUMNS)
+        );
+    }
+
+    // a scratch directory, emptied first
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("collect-info-test-{}-{}", std::process::id(), name));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // xorshift, incompressible enough to be stored
+    fn noise(length: usize) -> Vec<u8> {
+        let mut state: u64 = 0x9e3779b97f4a7c15;
+        (0..length).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        }).collect()
+    }
+
+    fn gzip(content: &[u8]) -> Vec<u8> {
+        let mut writer = GzipWriter::new(Vec::new());
+        writer.write_all(content).unwrap();
+        writer.finish().unwrap()
+    }
+
+    fn gunzip(compressed: &[u8]) -> io::Result<Vec<u8>> {
+        let mut content: Vec<u8> = Vec::new();
+        GzipReader::new(compressed).read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    // BTYPE of the first block, following the 10 byte gzip header
+    fn first_block_type(compressed: &[u8]) -> u8 {
+        (compressed[10] >> 1) & 3
+    }
+
+    #[test]
+    fn deflate_round_trips() {
+        let empty = gzip(&[]);
+        assert_eq!(Vec::<u8>::new(), gunzip(&empty).unwrap());
+
+        let stored = noise(200_000);
+        let compressed = gzip(&stored);
+        assert_eq!(0, first_block_type(&compressed));
+        assert_eq!(stored, gunzip(&compressed).unwrap());
+
+        let text: Vec<u8> = (0..20_000).flat_map(|i| format!("INFO  [main] 2024-03-01 14:05:09,{:03} line {}\n", i % 1000, i % 7).into_bytes()).collect();
+        assert!(DEFLATE_BLOCK < text.len());
+        let compressed = gzip(&text);
+        assert_eq!(2, first_block_type(&compressed));
+        assert!(compressed.len() < text.len() / 10);
+        assert_eq!(text, gunzip(&compressed).unwrap());
+
+        // python's gzip.compress(b'hello, hello, hello fixed huffman\n', mtime=0), a fixed Huffman block
+        let fixed: &[u8] = &[
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0xd7, 0x51, 0xc8, 0x40, 0xa2, 0x14, 0xd2,
+            0x32, 0x2b, 0x52, 0x53, 0x14, 0x32, 0x4a, 0xd3, 0xd2, 0x72, 0x13, 0xf3, 0xb8, 0x00, 0x71, 0xfb, 0x40, 0x22, 0x22, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(1, first_block_type(fixed));
+        assert_eq!(b"hello, hello, hello fixed huffman\n".to_vec(), gunzip(fixed).unwrap());
+
+        // a corrupted CRC is detected
+        let mut corrupt = gzip(b"some content");
+        let crc_at = corrupt.len() - 8;
+        corrupt[crc_at] ^= 0xff;
+        assert!(gunzip(&corrupt).is_err());
+    }
+
+    #[test]
+    fn gzip_agrees_with_system_gzip() {
+        let dir = test_dir("gzip");
+        let text: Vec<u8> = (0..5_000).flat_map(|i| format!("line {} of {}\n", i, i % 13).into_bytes()).chain(noise(10_000)).collect();
+        std::fs::write(dir.join("ours.gz"), gzip(&text)).unwrap();
+        let output = Command::new("gzip").arg("-dc").arg(dir.join("ours.gz")).output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(text, output.stdout);
+
+        std::fs::write(dir.join("theirs"), &text).unwrap();
+        let output = Command::new("gzip").arg("-c").arg(dir.join("theirs")).output().unwrap();
+        assert_eq!(text, gunzip(&output.stdout).unwrap());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn tar_round_trips_long_paths_and_symlinks() {
+        let dir = test_dir("tar");
+        let bundle = dir.join("bundle");
+        let long = format!("{}/{}", "nested-directory-name".repeat(6), "table-with-a-long-name".repeat(6));
+        std::fs::create_dir_all(bundle.join(&long)).unwrap();
+        std::fs::write(bundle.join(&long).join("Statistics.db"), noise(3_000)).unwrap();
+        std::fs::write(bundle.join("empty.txt"), b"").unwrap();
+        std::fs::write(bundle.join("schema.cql"), b"CREATE KEYSPACE ks;\n").unwrap();
+        std::os::unix::fs::symlink(format!("/var/lib/cassandra/{}", "long-link-target".repeat(10)), bundle.join("link")).unwrap();
+
+        let archive = dir.join("bundle.tar.gz");
+        let written = write_archive(&bundle, "bundle", &archive.to_string_lossy()).unwrap();
+        assert!(written.iter().any(|entry| entry.path == format!("bundle/{}/Statistics.db", long) && 3_000 == entry.size));
+        assert!(written.iter().any(|entry| "bundle/link" == entry.path && b'2' == entry.kind));
+        verify_archive(&archive.to_string_lossy(), &written).unwrap();
+
+        // system tar reads the PAX paths and link targets
+        let extracted = dir.join("extracted");
+        std::fs::create_dir_all(&extracted).unwrap();
+        let status = Command::new("tar").arg("-xzf").arg(&archive).arg("-C").arg(&extracted).status().unwrap();
+        assert!(status.success());
+        assert_eq!(noise(3_000), std::fs::read(extracted.join("bundle").join(&long).join("Statistics.db")).unwrap());
+        assert_eq!(std::fs::read_link(bundle.join("link")).unwrap(), std::fs::read_link(extracted.join("bundle/link")).unwrap());
+
+        // and an archive written by system tar (PAX format) is read the same
+        let theirs = dir.join("theirs.tar");
+        let status = Command::new("tar").arg("--format=pax").arg("-cf").arg(&theirs).arg("-C").arg(&dir).arg("bundle").status().unwrap();
+        assert!(status.success());
+        let read = read_tar_entries(&mut File::open(&theirs).unwrap()).unwrap();
+        let statistics = read.iter().find(|entry| entry.path == format!("bundle/{}/Statistics.db", long)).unwrap();
+        assert_eq!(written.iter().find(|entry| entry.path == statistics.path), Some(statistics));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    fn sha256(content: &[u8]) -> String {
+        let mut sha256 = Sha256::new();
+        sha256.update(content);
+        sha256.finish()
+    }
+
+    #[test]
+    fn sha256_fips_180_vectors() {
+        assert_eq!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad", sha256(b"abc"));
+        assert_eq!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855", sha256(b""));
+        assert_eq!("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1", sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"));
+        // a million 'a's, fed in uneven pieces across the block boundaries
+        let mut million = Sha256::new();
+        let a = vec![b'a'; 1_000];
+        (0..1_000).for_each(|i| {
+            million.update(&a[..i % 100]);
+            million.update(&a[i % 100..]);
+        });
+        assert_eq!(1_000_000, million.length);
+        assert_eq!("cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0", million.finish());
+    }
+
+    #[test]
+    fn manifest_paths_with_tabs_and_newlines_round_trip() {
+        let dir = test_dir("manifest");
+        let odd = "logs/odd\tname\nwith\\escapes.log";
+        std::fs::create_dir_all(dir.join("logs")).unwrap();
+        std::fs::write(dir.join(odd), b"abc").unwrap();
+        std::fs::write(dir.join("schema.cql"), b"").unwrap();
+        let artifact_dir = dir.to_string_lossy().to_string();
+        assert_eq!(2, write_bundle_manifest(&artifact_dir, &[("host".to_string(), "node1".to_string())], &HashMap::new()).unwrap());
+
+        let (header, files) = read_bundle_manifest(&format!("{}/{}", artifact_dir, BUNDLE_MANIFEST)).unwrap();
+        assert_eq!(vec![("host".to_string(), "node1".to_string())], header);
+        assert_eq!(vec![odd, "schema.cql"], files.iter().map(|file| file.path.as_str()).collect::<Vec<&str>>());
+        assert_eq!(sha256(b"abc"), files[0].sha256);
+        assert_eq!(3, files[0].size);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn tar_gnu_long_names() {
+        let dir = test_dir("gnu-tar");
+        let long = format!("bundle/{}/{}", "keyspace-name".repeat(10), "Data.db");
+        std::fs::create_dir_all(dir.join(&long).parent().unwrap()).unwrap();
+        std::fs::write(dir.join(&long), b"sstable").unwrap();
+        std::os::unix::fs::symlink("target-".repeat(20), dir.join("bundle/link")).unwrap();
+        let archive = dir.join("gnu.tar");
+        let status = Command::new("tar").arg("--format=gnu").arg("-cf").arg(&archive).arg("-C").arg(&dir).arg("bundle").status().unwrap();
+        assert!(status.success());
+
+        let read = read_tar_entries(&mut File::open(&archive).unwrap()).unwrap();
+        let data = read.iter().find(|entry| entry.path == long).unwrap();
+        assert_eq!((b'0', 7), (data.kind, data.size));
+        assert!(read.iter().any(|entry| "bundle/link" == entry.path && b'2' == entry.kind));
+        assert!(!read.iter().any(|entry| b'L' == entry.kind || b'K' == entry.kind || entry.path.contains("LongLink")));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    // a zip of (name, content, deflated) entries, with data descriptors when streamed, and the sizes and offsets in zip64 extra fields when zip64
+    fn zip(entries: &[(&str, &[u8], bool)], streamed: bool, zip64: bool) -> Vec<u8> {
+        let (mut archive, mut directory) = (Vec::new(), Vec::new());
+        for (name, content, deflated) in entries.iter() {
+            let mut crc = Crc32::new();
+            crc.update(content);
+            let compressed = if *deflated { gzip(content)[10..].to_vec() } else { content.to_vec() };
+            let compressed = &compressed[..compressed.len() - if *deflated { 8 } else { 0 }];
+            let (crc, method, flags, offset) = (crc.finish(), if *deflated { 8u16 } else { 0 }, if streamed { 8u16 } else { 0 }, archive.len() as u64);
+            // from the version needed to the uncompressed size, the same in the local header and the central directory
+            let header = |archive: &mut Vec<u8>, known: bool| {
+                archive.extend([20u16, flags, method, 0, 0].iter().flat_map(|value| value.to_le_bytes()));
+                let (crc, compressed_size, size) = if known { (crc, compressed.len() as u32, content.len() as u32) } else { (0, 0, 0) };
+                archive.extend([crc, compressed_size, size].iter().flat_map(|value| value.to_le_bytes()));
+            };
+            archive.extend(0x04034b50u32.to_le_bytes());
+            header(&mut archive, !streamed);
+            archive.extend([name.len() as u16, 0].iter().flat_map(|value| value.to_le_bytes()));
+            archive.extend(name.as_bytes());
+            archive.extend(compressed);
+            if streamed {
+                archive.extend([0x08074b50, crc, compressed.len() as u32, content.len() as u32].iter().flat_map(|value| value.to_le_bytes()));
+            }
+
+            directory.extend(0x02014b50u32.to_le_bytes());
+            directory.extend(20u16.to_le_bytes());
+            header(&mut directory, true);
+            let extra: Vec<u8> = if zip64 {
+                directory.truncate(directory.len() - 8);
+                directory.extend([0xffff_ffffu32; 2].iter().flat_map(|value| value.to_le_bytes()));
+                [1u16, 24].iter().flat_map(|value| value.to_le_bytes()).chain([content.len() as u64, compressed.len() as u64, offset].iter().flat_map(|value| value.to_le_bytes())).collect()
+            } else {
+                Vec::new()
+            };
+            directory.extend([name.len() as u16, extra.len() as u16, 0, 0, 0].iter().flat_map(|value| value.to_le_bytes()));
+            directory.extend(0u32.to_le_bytes());
+            directory.extend(if zip64 { 0xffff_ffff } else { offset as u32 }.to_le_bytes());
+            directory.extend(name.as_bytes());
+            directory.extend(extra);
+        }
+        let (directory_offset, directory_size, count) = (archive.len() as u64, directory.len() as u64, entries.len() as u64);
+        archive.extend(directory);
+        if zip64 {
+            let record = archive.len() as u64;
+            archive.extend(0x06064b50u32.to_le_bytes());
+            archive.extend(44u64.to_le_bytes());
+            archive.extend([45u16, 45].iter().flat_map(|value| value.to_le_bytes()));
+            archive.extend([0u32, 0].iter().flat_map(|value| value.to_le_bytes()));
+            archive.extend([count, count, directory_size, directory_offset].iter().flat_map(|value| value.to_le_bytes()));
+            archive.extend(0x07064b50u32.to_le_bytes());
+            archive.extend(0u32.to_le_bytes());
+            archive.extend(record.to_le_bytes());
+            archive.extend(1u32.to_le_bytes());
+        }
+        archive.extend(0x06054b50u32.to_le_bytes());
+        let small_count = if zip64 { 0xffff } else { count as u16 };
+        archive.extend([0u16, 0, small_count, small_count].iter().flat_map(|value| value.to_le_bytes()));
+        let (small_size, small_offset) = if zip64 { (0xffff_ffff, 0xffff_ffff) } else { (directory_size as u32, directory_offset as u32) };
+        archive.extend([small_size, small_offset].iter().flat_map(|value| value.to_le_bytes()));
+        archive.extend(0u16.to_le_bytes());
+        archive
+    }
+
+    fn read_zip(path: &Path) -> io::Result<Vec<(String, Vec<u8>)>> {
+        let mut read = Vec::new();
+        for entry in read_zip_directory(&mut File::open(path)?)? {
+            let mut content = Vec::new();
+            ZipEntryReader::open(path, &entry)?.read_to_end(&mut content)?;
+            read.push((entry.name, content));
+        }
+        Ok(read)
+    }
+
+    #[test]
+    fn zip_stored_and_deflated_entries() {
+        let dir = test_dir("zip");
+        let text: Vec<u8> = (0..5_000).flat_map(|i| format!("INFO  [main] 2024-03-01 14:05:09,000 Foo.java:1 - line {}\n", i).into_bytes()).collect();
+        let expected = vec![("system.log.1".to_string(), text.clone()), ("debug.log.1".to_string(), noise(1_000)), ("empty.log".to_string(), Vec::new())];
+        let entries: &[(&str, &[u8], bool)] = &[("system.log.1", &text, true), ("debug.log.1", &noise(1_000), false), ("empty.log", b"", false)];
+        for (streamed, zip64) in [(false, false), (true, false), (false, true), (true, true)].iter() {
+            let path = dir.join(format!("logs-{}-{}.zip", streamed, zip64));
+            std::fs::write(&path, zip(entries, *streamed, *zip64)).unwrap();
+            assert_eq!(expected, read_zip(&path).unwrap(), "streamed {}, zip64 {}", streamed, zip64);
+            assert!(Command::new("unzip").arg("-tq").arg(&path).stdout(Stdio::null()).status().unwrap().success());
+            let directory = read_zip_directory(&mut File::open(&path).unwrap()).unwrap();
+            assert_eq!((8, 0), (directory[0].method, directory[1].method));
+            assert!(directory[0].compressed_size < directory[0].size / 10);
+        }
+
+        // system zip, streaming stdin to stdout, so with a data descriptor (and zip64 local extra fields)
+        std::fs::write(dir.join("system.log.1"), &text).unwrap();
+        let output = Command::new("zip").arg("-q").arg("-").arg("-").stdin(File::open(dir.join("system.log.1")).unwrap()).output().unwrap();
+        assert!(output.status.success());
+        assert_ne!(0, output.stdout[6] & 8);
+        std::fs::write(dir.join("system.zip"), &output.stdout).unwrap();
+        assert_eq!(vec![("-".to_string(), text.clone())], read_zip(&dir.join("system.zip")).unwrap());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn zip_corruption_is_an_error() {
+        let dir = test_dir("zip-corrupt");
+        let text: Vec<u8> = (0..1_000).flat_map(|i| format!("line {}\n", i).into_bytes()).collect();
+        let archive = zip(&[("system.log.1", &text, true), ("debug.log.1", b"debug", false)], false, false);
+        let path = dir.join("corrupt.zip");
+        let directory_offset = le32(&archive, archive.len() - 6) as usize;
+
+        // a central directory entry without its signature
+        let mut corrupt = archive.clone();
+        corrupt[directory_offset] ^= 0xff;
+        std::fs::write(&path, &corrupt).unwrap();
+        assert_eq!("bad zip central directory", read_zip(&path).unwrap_err().to_string());
+        // more entries than the central directory holds
+        let mut corrupt = archive.clone();
+        let count_at = archive.len() - 12;
+        corrupt[count_at] = 3;
+        std::fs::write(&path, &corrupt).unwrap();
+        assert!(read_zip(&path).is_err());
+        // a central directory past the end of the file, or so large its end overflows
+        std::fs::write(&path, &archive[..directory_offset]).unwrap();
+        assert!(read_zip(&path).is_err());
+        let mut oversize = zip(&[("system.log.1", &text, true)], false, true);
+        let record_at = le64(&oversize, oversize.len() - 34) as usize;
+        oversize[record_at + 40..record_at + 56].copy_from_slice(&[0xff; 16]);
+        std::fs::write(&path, &oversize).unwrap();
+        assert_eq!("zip central directory past the end of the file (truncated)", read_zip(&path).unwrap_err().to_string());
+        // a zip64 locator pointing to something else
+        let mut misplaced = zip(&[("system.log.1", &text, true)], false, true);
+        let locator_at = misplaced.len() - 34;
+        misplaced[locator_at..locator_at + 8].copy_from_slice(&0u64.to_le_bytes());
+        std::fs::write(&path, &misplaced).unwrap();
+        assert_eq!("bad zip64 end of central directory", read_zip(&path).unwrap_err().to_string());
+        // a flipped bit in the deflated content, or in the stored one, fails the checksum
+        let mut corrupt = archive.clone();
+        corrupt[30 + "system.log.1".len() + 20] ^= 0x01;
+        std::fs::write(&path, &corrupt).unwrap();
+        assert!(read_zip(&path).is_err());
+        let mut corrupt = archive.clone();
+        corrupt[directory_offset - 1] ^= 0x01;
+        std::fs::write(&path, &corrupt).unwrap();
+        assert_eq!("zip entry checksum mismatch (truncated or corrupt)", read_zip(&path).unwrap_err().to_string());
+        std::fs::write(&path, b"not a zip").unwrap();
+        assert_eq!("not a zip (no end of central directory)", read_zip(&path).unwrap_err().to_string());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    // every REQUIRED_ARTIFACTS present, with MANIFEST
+    fn complete_bundle(name: &str) -> PathBuf {
+        let dir = test_dir(name).join("bundle");
+        for (path, content) in [
+            ("schema.cql", "CREATE KEYSPACE ks;\n"),
+            ("nodetool/status.txt", "UN  10.0.0.1\n"),
+            ("metrics.jmx", "org_apache_cassandra_metrics 1\n"),
+            ("conf/cassandra.yaml", "cluster_name: test\n"),
+            ("logs/system.log", "INFO  [main] started\n"),
+        ].iter() {
+            std::fs::create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+            std::fs::write(dir.join(path), content).unwrap();
+        }
+        write_bundle_manifest(&dir.to_string_lossy(), &[("host".to_string(), "node1".to_string())], &HashMap::new()).unwrap();
+        dir
+    }
+
+    #[test]
+    fn verify_exit_codes() {
+        let dir = complete_bundle("verify-codes");
+        let bundle = dir.to_string_lossy().to_string();
+        assert_eq!(EXIT_COMPLETE, verify_bundle(&bundle));
+        // files added after MANIFEST was written are only reported
+        std::fs::write(dir.join("extra.txt"), b"extra").unwrap();
+        assert_eq!(EXIT_COMPLETE, verify_bundle(&bundle));
+
+        std::fs::write(dir.join("metrics.jmx"), b"").unwrap();
+        std::fs::write(dir.join("logs/system.log"), b"").unwrap();
+        write_bundle_manifest(&bundle, &[], &HashMap::new()).unwrap();
+        assert_eq!(EXIT_PARTIAL, verify_bundle(&bundle));
+
+        ["schema.cql", "nodetool/status.txt", "conf/cassandra.yaml"].iter().for_each(|path| std::fs::write(dir.join(path), b"").unwrap());
+        write_bundle_manifest(&bundle, &[], &HashMap::new()).unwrap();
+        assert_eq!(EXIT_NOTHING_USEFUL, verify_bundle(&bundle));
+        std::fs::remove_dir_all(dir.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn verify_detects_missing_changed_and_truncated() {
+        let dir = complete_bundle("verify-corrupt");
+        let bundle = dir.to_string_lossy().to_string();
+        let archive = format!("{}.tar.gz", bundle);
+        let written = write_archive(&dir, "bundle", &archive).unwrap();
+        assert_eq!(EXIT_COMPLETE, verify_bundle(&archive));
+        verify_archive(&archive, &written).unwrap();
+
+        let content = std::fs::read(&archive).unwrap();
+        let truncated = format!("{}/truncated.tar.gz", dir.parent().unwrap().display());
+        std::fs::write(&truncated, &content[..content.len() / 2]).unwrap();
+        assert_eq!(1, verify_bundle(&truncated));
+
+        std::fs::write(dir.join("conf/cassandra.yaml"), b"cluster_name: edit\n").unwrap();
+        assert_eq!(1, verify_bundle(&bundle));
+        std::fs::write(dir.join("conf/cassandra.yaml"), b"cluster_name: test\n").unwrap();
+        assert_eq!(EXIT_COMPLETE, verify_bundle(&bundle));
+        // the same size, different content
+        std::fs::write(dir.join("conf/cassandra.yaml"), b"cluster_name: TEST\n").unwrap();
+        assert_eq!(1, verify_bundle(&bundle));
+        std::fs::write(dir.join("conf/cassandra.yaml"), b"cluster_name: test\n").unwrap();
+
+        std::fs::remove_file(dir.join("logs/system.log")).unwrap();
+        assert_eq!(1, verify_bundle(&bundle));
+        std::fs::remove_dir_all(dir.parent().unwrap()).ok();
+    }
+}